@@ -23,7 +23,13 @@ pub fn fluent_ecs_bench(c: &mut Criterion) {
     };
 
     let mut group = c.benchmark_group("fluent_ecs_bench");
-    for test_case in ["postfix/smtpd_auth_failed", "metallb/speaker_partial_join", "kubernetes_statefulset"].iter() {
+    for test_case in [
+        "postfix/smtpd_auth_failed",
+        "metallb/speaker_partial_join",
+        "kubernetes_statefulset",
+    ]
+    .iter()
+    {
         group.bench_with_input(
             BenchmarkId::from_parameter(test_case),
             test_case,
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::sync::OnceLock;
+
+use log::warn;
+use serde_derive::Deserialize;
+
+/// Path of the configuration file if the environment variable `FLUENT_ECS_CONFIG` is not set.
+/// It is relative to the working directory. Only native builds can read it, WASM builds for
+/// `wasm32-unknown-unknown` have no filesystem and use the embedded configuration.
+pub const DEFAULT_CONFIG_PATH: &str = "fluent-ecs.json";
+
+/// A configuration embedded at build time, e.g. with
+/// `FLUENT_ECS_EMBEDDED_CONFIG="$(cat fluent-ecs.json)" cargo build`.
+/// It is used when no configuration file can be found at runtime.
+const EMBEDDED_CONFIG: Option<&str> = option_env!("FLUENT_ECS_EMBEDDED_CONFIG");
+
+static GLOBAL_CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Returns the configuration loaded on first use.
+pub fn global() -> &'static Config {
+    GLOBAL_CONFIG.get_or_init(Config::load)
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub parser_detection: ParserDetection,
+    /// The parser used when no parser could be detected.
+    pub default_parser: Option<String>,
+    /// The parsers that may be used. All parsers are enabled if this is not set.
+    pub enabled_parsers: Option<Vec<String>>,
+    pub output: Output,
+    /// The IANA timezone of timestamps that carry none, like those of postfix and klog, e.g.
+    /// `Europe/Berlin`. UTC if not set.
+    pub timezone: Option<String>,
+    /// Overrides the `event.severity` for a log level per parser, e.g.
+    /// `{"etcd": {"warn": 350}}`.
+    pub severity: HashMap<String, HashMap<String, u32>>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParserDetection {
+    /// Kubernetes annotations that name the parser. Evaluated first.
+    pub annotations: Vec<String>,
+    /// Kubernetes labels that name the parser. Evaluated after the annotations.
+    pub labels: Vec<String>,
+    /// fluent-bit tag patterns that select a parser. Evaluated after the labels.
+    pub tags: Vec<TagParser>,
+    /// Whether the parser is guessed from the content of the record if nothing else matched.
+    pub sniff: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TagParser {
+    /// A fluent-bit tag pattern. A `*` matches any sequence of characters.
+    pub pattern: String,
+    pub parser: String,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Output {
+    /// What to do with fields that have no ECS mapping. Formerly named `misc`.
+    #[serde(alias = "misc")]
+    pub non_ecs_fields: NonEcsPolicy,
+    /// Overrides `non_ecs_fields` per parser, e.g. `{"etcd": "nest"}`.
+    pub non_ecs_fields_per_parser: HashMap<String, NonEcsPolicy>,
+    /// Whether the fluent-bit tag is added as `labels.fluentbit_tag`.
+    pub fluentbit_tag: bool,
+    /// Whether the parser, how it was detected and how much of the record it understood are added
+    /// as `labels.fluent_ecs_parser`, `labels.fluent_ecs_detection` and `labels.fluent_ecs_outcome`.
+    pub pipeline_metadata: bool,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum NonEcsPolicy {
+    /// Adds the fields as `"key:value"` strings to the array `misc`. Fields of the input that a
+    /// parser does not know are kept at the top level. Formerly named `keep`.
+    #[serde(alias = "keep")]
+    Misc,
+    /// Moves the fields with their original types to an object named after `event.module`,
+    /// e.g. `etcd.hash`.
+    Nest,
+    /// Adds the fields as keywords to `labels`.
+    Labels,
+    /// Removes the fields, the unknown fields of the input and its `misc` array.
+    Drop,
+}
+
+impl Config {
+    /// Loads the configuration file from the path in `FLUENT_ECS_CONFIG` or [DEFAULT_CONFIG_PATH].
+    /// Falls back to the configuration embedded at build time and then to the defaults.
+    pub fn load() -> Self {
+        let path =
+            env::var("FLUENT_ECS_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+        if let Ok(content) = fs::read(&path) {
+            match serde_json::from_slice(&content) {
+                Ok(config) => return config,
+                Err(err) => warn!("fluent-ecs config '{}' is invalid: {}", path, err),
+            }
+        }
+
+        if let Some(content) = EMBEDDED_CONFIG {
+            match serde_json::from_str(content) {
+                Ok(config) => return config,
+                Err(err) => warn!("embedded fluent-ecs config is invalid: {}", err),
+            }
+        }
+
+        Config::default()
+    }
+
+    pub fn from_json(json: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(json)
+    }
+
+    pub fn is_parser_enabled(&self, parser: &str) -> bool {
+        match &self.enabled_parsers {
+            Some(enabled_parsers) => enabled_parsers.iter().any(|p| p == parser),
+            None => true,
+        }
+    }
+
+    /// The policy for fields without ECS mapping of a parser.
+    pub fn non_ecs_policy(&self, parser: Option<&str>) -> NonEcsPolicy {
+        parser
+            .and_then(|parser| self.output.non_ecs_fields_per_parser.get(parser))
+            .copied()
+            .unwrap_or(self.output.non_ecs_fields)
+    }
+
+    /// The configured severity for a log level of a parser if there is one.
+    pub fn severity(&self, parser: &str, level: &str) -> Option<u32> {
+        self.severity.get(parser)?.get(level).copied()
+    }
+}
+
+impl Default for ParserDetection {
+    fn default() -> Self {
+        let keyword_tags = [
+            "postfix",
+            "etcd",
+            "keycloak",
+            "metallb",
+            "kubernetes-dashboard-metrics-scraper",
+            "klog",
+            "logfmt",
+        ]
+        .into_iter()
+        .flat_map(|keyword| {
+            [
+                TagParser {
+                    pattern: keyword.to_string(),
+                    parser: keyword.to_string(),
+                },
+                TagParser {
+                    pattern: format!("{}.*", keyword),
+                    parser: keyword.to_string(),
+                },
+            ]
+        })
+        .collect();
+
+        ParserDetection {
+            annotations: vec!["fluent-ecs.bieniek-it.de/parser".to_string()],
+            labels: vec![
+                "app.kubernetes.io/name".to_string(),
+                "component".to_string(),
+            ],
+            tags: keyword_tags,
+            sniff: false,
+        }
+    }
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Output {
+            non_ecs_fields: NonEcsPolicy::Misc,
+            non_ecs_fields_per_parser: HashMap::new(),
+            fluentbit_tag: true,
+            pipeline_metadata: true,
+        }
+    }
+}
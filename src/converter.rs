@@ -0,0 +1,100 @@
+use std::sync::{Arc, OnceLock, RwLock};
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::config::Config;
+use crate::model::FluentBitJson;
+use crate::{etcd, keycloak, klog, kubernetes_dashboard, logfmt, metallb, postfix};
+
+/// Converts the logs of one application to ECS.
+///
+/// Converters are selected by their name or one of their aliases, e.g. from a Kubernetes label.
+/// Additional converters can be added with [register_converter] and removed again with
+/// [unregister_converter].
+pub trait LogConverter: Send + Sync {
+    /// The name used to select the converter and to look up its configuration.
+    fn name(&self) -> &str;
+
+    /// Further names that select the converter.
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    fn convert(&self, json: &mut FluentBitJson, context: &Context) -> Outcome;
+}
+
+/// Information about the record that is not part of the record itself.
+pub struct Context<'a> {
+    /// The time fluent-bit assigned to the record.
+    pub event_date: &'a DateTime<FixedOffset>,
+    pub tag: &'a str,
+    pub config: &'a Config,
+}
+
+/// How much of a record a converter could map to ECS.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// The record was fully understood.
+    Full,
+    /// Parts of the record could not be mapped.
+    Partial,
+    /// The record could not be parsed and was passed on mostly unchanged.
+    Fallback,
+}
+
+impl Outcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Full => "full",
+            Outcome::Partial => "partial",
+            Outcome::Fallback => "fallback",
+        }
+    }
+}
+
+static REGISTRY: OnceLock<RwLock<Vec<Arc<dyn LogConverter>>>> = OnceLock::new();
+
+fn registry() -> &'static RwLock<Vec<Arc<dyn LogConverter>>> {
+    REGISTRY.get_or_init(|| {
+        RwLock::new(vec![
+            Arc::new(metallb::MetallbConverter),
+            Arc::new(etcd::EtcdConverter),
+            Arc::new(postfix::PostfixConverter),
+            Arc::new(keycloak::KeycloakConverter),
+            Arc::new(kubernetes_dashboard::MetricsScraperConverter),
+            Arc::new(klog::KlogConverter),
+            Arc::new(logfmt::LogfmtConverter),
+        ])
+    })
+}
+
+/// Adds a converter. It takes precedence over converters registered before with the same name
+/// or alias, including the built-in ones.
+pub fn register_converter(converter: impl LogConverter + 'static) {
+    registry()
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(0, Arc::new(converter));
+}
+
+/// Removes the converter registered last with the name. A built-in converter that was shadowed
+/// by it is used again.
+pub fn unregister_converter(name: &str) -> Option<Arc<dyn LogConverter>> {
+    let mut registry = registry()
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let index = registry
+        .iter()
+        .position(|converter| converter.name() == name)?;
+    Some(registry.remove(index))
+}
+
+/// Returns the converter selected by the name or alias.
+pub fn find_converter(name: &str) -> Option<Arc<dyn LogConverter>> {
+    registry()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .find(|converter| converter.name() == name || converter.aliases().contains(&name))
+        .cloned()
+}
@@ -1,9 +1,24 @@
 use chrono::{DateTime, FixedOffset};
 use serde_json::Value;
 
+use crate::config::Config;
+use crate::converter::{Context, LogConverter, Outcome};
 use crate::model::FluentBitJson;
 
-pub fn convert_etcd_logs(json: &mut FluentBitJson) {
+pub struct EtcdConverter;
+
+impl LogConverter for EtcdConverter {
+    fn name(&self) -> &str {
+        "etcd"
+    }
+
+    fn convert(&self, json: &mut FluentBitJson, context: &Context) -> Outcome {
+        convert_etcd_logs(json, context.config);
+        Outcome::Full
+    }
+}
+
+fn convert_etcd_logs(json: &mut FluentBitJson, config: &Config) {
     let level = json.other.remove("level").and_then(|level| match level {
         Value::String(level) => Some(level),
         _ => None,
@@ -12,7 +27,7 @@ pub fn convert_etcd_logs(json: &mut FluentBitJson) {
     // @timestamp
     match convert_ts(json) {
         TsParseResult::Ok(ts) => json.timestamp = Some(ts),
-        TsParseResult::Err(ts) => json.misc.push(format!("ts:{}", ts)),
+        TsParseResult::Err(ts) => json.add_non_ecs("ts", ts),
         TsParseResult::None => {}
     };
 
@@ -31,11 +46,10 @@ pub fn convert_etcd_logs(json: &mut FluentBitJson) {
         event.kind = Some("event".to_string());
         event.module = Some("etcd".to_string());
         event.category = vec!["database".to_string()];
-        match &level {
-            Some(level) => {
-                event.severity = convert_severity(&level);
-            }
-            _ => {}
+        if let Some(level) = &level {
+            event.severity = config
+                .severity("etcd", level)
+                .or_else(|| convert_severity(level));
         };
         if let Some(Value::String(duration)) = took {
             event.duration = convert_duration(duration);
@@ -46,33 +60,29 @@ pub fn convert_etcd_logs(json: &mut FluentBitJson) {
     let caller = convert_caller(json);
     {
         let log = json.log();
-        match level {
-            Some(level) => log.level = Some(level),
-            _ => {}
+        if let Some(level) = level {
+            log.level = Some(level)
         };
-        match caller {
-            Some((file, line)) => {
-                let orign_file = log.origin().file();
-                orign_file.name = Some(file);
-                orign_file.line = Some(line);
-            }
-            _ => {}
+        if let Some((file, line)) = caller {
+            let orign_file = log.origin().file();
+            orign_file.name = Some(file);
+            orign_file.line = Some(line);
         }
     }
 
     // Remove non-ecs mappings if they exist
-    json.move_key_to_misc("hash");
-    json.move_key_to_misc("compact-index");
-    json.move_key_to_misc("compact-revision");
-    json.move_key_to_misc("expected-duration");
-    json.move_key_to_misc("prefix");
-    json.move_key_to_misc("request");
-    json.move_key_to_misc("response");
-    json.move_key_to_misc("revision");
-    json.move_key_to_misc("current-db-size");
-    json.move_key_to_misc("current-db-size-bytes");
-    json.move_key_to_misc("current-db-size-in-use");
-    json.move_key_to_misc("current-db-size-in-use-bytes");
+    json.move_key_to_non_ecs("hash");
+    json.move_key_to_non_ecs("compact-index");
+    json.move_key_to_non_ecs("compact-revision");
+    json.move_key_to_non_ecs("expected-duration");
+    json.move_key_to_non_ecs("prefix");
+    json.move_key_to_non_ecs("request");
+    json.move_key_to_non_ecs("response");
+    json.move_key_to_non_ecs("revision");
+    json.move_key_to_non_ecs("current-db-size");
+    json.move_key_to_non_ecs("current-db-size-bytes");
+    json.move_key_to_non_ecs("current-db-size-in-use");
+    json.move_key_to_non_ecs("current-db-size-in-use-bytes");
 }
 
 fn convert_severity(level: &str) -> Option<u32> {
@@ -109,10 +119,10 @@ fn convert_ts(json: &mut FluentBitJson) -> TsParseResult {
 fn convert_duration(duration: String) -> Option<u64> {
     let (number, factor) = if let Some(number) = duration.strip_suffix("ms") {
         Some((number, 1000.0 * 1000.0))
-    } else if let Some(number) = duration.strip_suffix("s") {
-        Some((number, 1000.0 * 1000.0 * 1000.0))
     } else {
-        None
+        duration
+            .strip_suffix("s")
+            .map(|number| (number, 1000.0 * 1000.0 * 1000.0))
     }?;
 
     let duration = number.parse::<f64>().ok()?;
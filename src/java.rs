@@ -0,0 +1,103 @@
+use serde_json::{Map, Value};
+
+use crate::model::FluentBitJson;
+
+/// Maps the `exception` object and the `stackTrace` string of JSON logs of JBoss logging,
+/// e.g. by Quarkus, to `error.*`.
+///
+/// The `stackTrace` string is used as `error.stack_trace` as it was logged. Without it the stack
+/// trace is rendered from the `frames` of the exception and of all exceptions it was caused by.
+pub fn convert_exception(json: &mut FluentBitJson) {
+    let stack_trace = match json.other.remove("stackTrace") {
+        Some(Value::String(stack_trace)) => Some(stack_trace),
+        Some(stack_trace) => {
+            json.add_non_ecs("stackTrace", stack_trace);
+            None
+        }
+        None => None,
+    };
+
+    let exception = match json.other.remove("exception") {
+        Some(Value::Object(exception)) => Some(exception),
+        Some(exception) => {
+            json.add_non_ecs("exception", exception);
+            None
+        }
+        None => None,
+    };
+
+    if exception.is_none() && stack_trace.is_none() {
+        return;
+    }
+
+    let error = json.error();
+    if let Some(exception) = &exception {
+        if let Some(Value::String(exception_type)) = exception.get("exceptionType") {
+            error.type_val = Some(exception_type.clone());
+        }
+        if let Some(Value::String(message)) = exception.get("message") {
+            error.message = Some(message.clone());
+        }
+    }
+    error.stack_trace = stack_trace.or_else(|| exception.map(|e| render_stack_trace(&e)));
+}
+
+/// Renders an exception like `Throwable.printStackTrace()`.
+fn render_stack_trace(exception: &Map<String, Value>) -> String {
+    let mut stack_trace = String::new();
+    let mut exception = Some(exception);
+
+    while let Some(current) = exception {
+        if !stack_trace.is_empty() {
+            stack_trace.push_str("Caused by: ");
+        }
+        stack_trace.push_str(
+            current
+                .get("exceptionType")
+                .and_then(Value::as_str)
+                .unwrap_or("<unknown>"),
+        );
+        if let Some(message) = current.get("message").and_then(Value::as_str) {
+            stack_trace.push_str(": ");
+            stack_trace.push_str(message);
+        }
+        stack_trace.push('\n');
+
+        let frames = current.get("frames").and_then(Value::as_array);
+        for frame in frames.into_iter().flatten() {
+            stack_trace.push_str("\tat ");
+            stack_trace.push_str(&render_frame(frame));
+            stack_trace.push('\n');
+        }
+
+        // "causedBy": {"exception": {...}}
+        exception = current
+            .get("causedBy")
+            .and_then(|caused_by| caused_by.get("exception"))
+            .and_then(Value::as_object);
+    }
+
+    stack_trace.truncate(stack_trace.trim_end().len());
+    stack_trace
+}
+
+/// Renders a frame like `StackTraceElement.toString()`.
+///
+/// The frames do not contain the source file, so it is rendered as `Unknown Source`. Like in Java
+/// the line number is left out without a file name, as stack trace parsers expect `(File.java:N)`
+/// or `(Unknown Source)`.
+fn render_frame(frame: &Value) -> String {
+    let class = frame
+        .get("class")
+        .and_then(Value::as_str)
+        .unwrap_or("<unknown>");
+    let method = frame
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or("<unknown>");
+    let source = match frame.get("line").and_then(Value::as_i64) {
+        Some(-2) => "Native Method",
+        _ => "Unknown Source",
+    };
+    format!("{}.{}({})", class, method, source)
+}
@@ -1,10 +1,14 @@
 use chrono::{DateTime, FixedOffset};
-use serde_json::Value;
+use serde_json::{Map, Value};
 
 use log::warn;
 use pest::Parser;
 use pest_derive::Parser;
 
+use crate::config::Config;
+use crate::converter::{Context, LogConverter, Outcome};
+use crate::java;
+use crate::model::ecs::Url;
 use crate::model::FluentBitJson;
 
 #[derive(Parser)]
@@ -24,7 +28,19 @@ value_char = {
 "#]
 struct KeycloakEventParser;
 
-pub fn convert_keycloak_logs(json: &mut FluentBitJson) {
+pub struct KeycloakConverter;
+
+impl LogConverter for KeycloakConverter {
+    fn name(&self) -> &str {
+        "keycloak"
+    }
+
+    fn convert(&self, json: &mut FluentBitJson, context: &Context) -> Outcome {
+        convert_keycloak_logs(json, context.config)
+    }
+}
+
+fn convert_keycloak_logs(json: &mut FluentBitJson, config: &Config) -> Outcome {
     let level = json.other.remove("level").and_then(|level| match level {
         Value::String(level) => Some(level),
         _ => None,
@@ -33,7 +49,7 @@ pub fn convert_keycloak_logs(json: &mut FluentBitJson) {
     // @timestamp
     match convert_ts(json) {
         TsParseResult::Ok(ts) => json.timestamp = Some(ts),
-        TsParseResult::Err(ts) => json.misc.push(format!("ts:{}", ts)),
+        TsParseResult::Err(ts) => json.add_non_ecs("ts", ts),
         TsParseResult::None => {}
     };
 
@@ -46,18 +62,17 @@ pub fn convert_keycloak_logs(json: &mut FluentBitJson) {
     event.module = Some("keycloak".to_string());
     event.module = Some("keycloak".to_string());
     event.category = vec!["iam".to_string()];
-    match &level {
-        Some(level) => {
-            event.severity = convert_severity(&level);
-        }
-        _ => {}
+    if let Some(level) = &level {
+        event.severity = config
+            .severity("keycloak", level)
+            .or_else(|| convert_severity(level));
     };
     match sequence {
         Some(Value::Number(sequence)) => {
             event.sequence = sequence.as_u64();
         }
         Some(sequence) => {
-            json.misc.push(format!("sequence:{}", sequence.to_string()));
+            json.add_non_ecs("sequence", sequence);
         }
         _ => {}
     }
@@ -65,16 +80,15 @@ pub fn convert_keycloak_logs(json: &mut FluentBitJson) {
     // log
     let logger_name = json.other.remove("loggerName");
     let log = json.log();
-    match level {
-        Some(level) => log.level = Some(level),
-        _ => {}
+    if let Some(level) = level {
+        log.level = Some(level)
     };
     match logger_name {
         Some(Value::String(logger)) => {
             log.logger = Some(logger);
         }
         Some(logger) => {
-            json.misc.push(format!("logger:{}", logger.to_string()));
+            json.add_non_ecs("logger", logger);
         }
         _ => {}
     }
@@ -85,8 +99,7 @@ pub fn convert_keycloak_logs(json: &mut FluentBitJson) {
             json.process().name = Some(process_name);
         }
         Some(process_name) => {
-            json.misc
-                .push(format!("processName:{}", process_name.to_string()));
+            json.add_non_ecs("processName", process_name);
         }
         _ => {}
     }
@@ -97,16 +110,15 @@ pub fn convert_keycloak_logs(json: &mut FluentBitJson) {
                     json.process().pid = Some(val_32);
                 }
                 _ => {
-                    json.misc.push(format!("processId:{}", val_64));
+                    json.add_non_ecs("processId", val_64);
                 }
             },
             _ => {
-                json.misc
-                    .push(format!("processId:{}", process_id.to_string()));
+                json.add_non_ecs("processId", process_id);
             }
         },
         Some(process_id) => {
-            json.misc.push(format!("process_id:{}", process_id));
+            json.add_non_ecs("process_id", process_id);
         }
         _ => {}
     }
@@ -115,28 +127,21 @@ pub fn convert_keycloak_logs(json: &mut FluentBitJson) {
             json.process().thread().name = Some(thread_name);
         }
         Some(thread_name) => {
-            json.misc
-                .push(format!("threadName:{}", thread_name.to_string()));
+            json.add_non_ecs("threadName", thread_name);
         }
         _ => {}
     }
     match json.other.remove("threadId") {
         Some(Value::Number(thread_id)) => match thread_id.as_u64() {
-            Some(val_64) => match val_64.try_into() {
-                Ok(val_32) => {
-                    json.process().thread().id = Some(val_32);
-                }
-                _ => {
-                    json.misc.push(format!("threadId:{}", val_64));
-                }
-            },
+            Some(val_64) => {
+                json.process().thread().id = Some(val_64);
+            }
             _ => {
-                json.misc
-                    .push(format!("threadId:{}", thread_id.to_string()));
+                json.add_non_ecs("threadId", thread_id);
             }
         },
         Some(process_id) => {
-            json.misc.push(format!("threadId:{}", process_id));
+            json.add_non_ecs("threadId", process_id);
         }
         _ => {}
     }
@@ -145,59 +150,115 @@ pub fn convert_keycloak_logs(json: &mut FluentBitJson) {
     match json.other.remove("hostName") {
         Some(Value::String(host_name)) => json.host().hostname = Some(host_name),
         Some(host_name) => {
-            json.misc
-                .push(format!("hostName:{}", host_name.to_string()));
+            json.add_non_ecs("hostName", host_name);
         }
         _ => {}
     }
 
+    // error
+    java::convert_exception(json);
+
+    // mapped diagnostic context
+    convert_mdc(json);
+    match json.other.remove("ndc") {
+        Some(Value::String(ndc)) if ndc.is_empty() => {}
+        Some(ndc) => json.add_structured_non_ecs("ndc", ndc),
+        None => {}
+    }
+
     // Remove non-ecs mappings if they exist
-    json.move_key_to_misc("mdc");
-    json.move_key_to_misc("ndc");
-    json.move_key_to_misc("loggerClassName");
+    json.move_key_to_non_ecs("loggerClassName");
 
     // parse events
-    if json
+    let event_log = json
         .log()
         .logger
         .as_ref()
-        .is_some_and(|s| s == "org.keycloak.events")
-    {
-        parse_event_log(json);
+        .is_some_and(|s| s == "org.keycloak.events");
+    if event_log && !parse_event_log(json) {
+        return Outcome::Partial;
+    }
+
+    Outcome::Full
+}
+
+/// Maps the well-known keys of the MDC of Keycloak and Quarkus. The remaining entries are kept
+/// as structured `mdc` field without ECS mapping.
+fn convert_mdc(json: &mut FluentBitJson) {
+    let mdc = match json.other.remove("mdc") {
+        Some(Value::Object(mdc)) => mdc,
+        Some(mdc) => {
+            json.add_non_ecs("mdc", mdc);
+            return;
+        }
+        None => return,
+    };
+
+    let mut remaining = Map::new();
+    for (key, value) in mdc {
+        let Value::String(value) = value else {
+            remaining.insert(key, value);
+            continue;
+        };
+        match key.as_str() {
+            // Quarkus OpenTelemetry
+            "traceId" => json.trace().id = Some(value),
+            "spanId" => json.span().id = Some(value),
+            // Keycloak with the option log-mdc-enabled
+            "kc.realmName" => json.user().domain = Some(value),
+            "kc.clientId" => json.service().target().name = Some(value),
+            "kc.userId" => json.user().id = Some(value),
+            "kc.ipAddress" => json.source().ip = Some(value),
+            _ => {
+                remaining.insert(key, Value::String(value));
+            }
+        }
+    }
+    if !remaining.is_empty() {
+        json.add_structured_non_ecs("mdc", remaining);
     }
 }
 
-fn parse_event_log(json: &mut FluentBitJson) {
+/// Returns false if the event log could not be parsed.
+fn parse_event_log(json: &mut FluentBitJson) -> bool {
     let message = json.message.clone();
 
-    match message {
-        Some(message) => match KeycloakEventParser::parse(Rule::event_log, &message) {
+    if let Some(message) = message {
+        match KeycloakEventParser::parse(Rule::event_log, &message) {
             Ok(pairs) => {
                 for pair in pairs {
-                    match pair.as_rule() {
-                        Rule::event_log => parse_event_log_rule(json, pair.into_inner()),
-                        _ => {}
+                    if pair.as_rule() == Rule::event_log {
+                        parse_event_log_rule(json, pair.into_inner())
                     }
                 }
             }
-            Err(err) => warn!("parsing Keycloak event log failed: {}", err),
-        },
-        None => {}
+            Err(err) => {
+                warn!("parsing Keycloak event log failed: {}", err);
+                return false;
+            }
+        }
     }
+    true
 }
 
 fn parse_event_log_rule(json: &mut FluentBitJson, pairs: pest::iterators::Pairs<'_, Rule>) {
+    // Details of the event without ECS mapping, e.g. the resource of admin events.
+    let mut details = Map::new();
     for pair in pairs {
-        match pair.as_rule() {
-            Rule::key_value_pair => {
-                parse_event_key_value_par(json, pair.into_inner());
-            }
-            _ => {}
+        if pair.as_rule() == Rule::key_value_pair {
+            parse_event_key_value_par(json, &mut details, pair.into_inner());
         }
     }
+    if !details.is_empty() {
+        json.add_structured_non_ecs("event", details);
+    }
 }
 
-fn parse_event_key_value_par(json: &mut FluentBitJson, pairs: pest::iterators::Pairs<'_, Rule>) {
+fn parse_event_key_value_par(
+    json: &mut FluentBitJson,
+    details: &mut Map<String, Value>,
+    pairs: pest::iterators::Pairs<'_, Rule>,
+) {
     let mut key: Option<&str> = None;
     let mut value: Option<&str> = None;
 
@@ -206,9 +267,8 @@ fn parse_event_key_value_par(json: &mut FluentBitJson, pairs: pest::iterators::P
             Rule::key => key = Some(pair.as_str()),
             Rule::value => {
                 for pair in pair.into_inner() {
-                    match pair.as_rule() {
-                        Rule::value_inner => value = Some(pair.as_str()),
-                        _ => {}
+                    if pair.as_rule() == Rule::value_inner {
+                        value = Some(pair.as_str())
                     }
                 }
             }
@@ -220,15 +280,48 @@ fn parse_event_key_value_par(json: &mut FluentBitJson, pairs: pest::iterators::P
         let value = unmask(value);
         match key {
             "type" => convert_event_type(json, value),
+            "operationType" => convert_operation_type(json, value),
+            "resourceType" => {
+                convert_resource_type(json, &value);
+                details.insert("resource_type".to_string(), Value::String(value));
+            }
+            "resourcePath" => {
+                // e.g. users/6c2a9f0e-b6a1-4f7e-9e0b-2e1f1a1c5d3e/role-mappings/realm
+                if let Some(user_id) = value
+                    .strip_prefix("users/")
+                    .and_then(|path| path.split('/').next())
+                    .filter(|id| !id.is_empty())
+                {
+                    json.user().target().id = Some(user_id.to_string());
+                }
+                details.insert("resource_path".to_string(), Value::String(value));
+            }
+            // The client the user or admin authenticated with. Admin events log the internal id
+            // of the client instead of its client id.
+            "clientId" if is_uuid(&value) => json.service().target().id = Some(value),
+            "clientId" => json.service().target().name = Some(value),
+            // Newer Keycloak versions log the realm name in addition to its id.
+            "realmId" => {
+                json.user().domain.get_or_insert(value);
+            }
+            "realmName" => json.user().domain = Some(value),
+            // The id of the authentication session.
+            "code_id" => json.transaction().id = Some(value),
+            "redirect_uri" => json.url = Some(Url::from_original(&value)),
             "ipAddress" => json.source().ip = Some(value),
-            "error" => json.error().message = Some(value),
+            "error" => {
+                json.event().outcome = Some("failure".to_string());
+                json.error().message = Some(value);
+            }
             "username" => json.user().name = Some(value),
             "userId" => {
                 if value != "null" {
                     json.user().id = Some(value);
                 }
             }
-            _ => {}
+            _ => {
+                details.insert(snake_case(key), Value::String(value));
+            }
         }
     }
 }
@@ -236,24 +329,134 @@ fn parse_event_key_value_par(json: &mut FluentBitJson, pairs: pest::iterators::P
 fn convert_event_type(json: &mut FluentBitJson, value: String) {
     let event = json.event();
 
+    // Every event type has an error variant, e.g. LOGIN_ERROR.
+    let (event_type, failed) = match value.strip_suffix("_ERROR") {
+        Some(event_type) => (event_type, true),
+        None => (value.as_str(), false),
+    };
+
+    if let Some((category, types)) = convert_event_taxonomy(event_type) {
+        if !event.category.iter().any(|c| c == category) {
+            event.category.push(category.to_string());
+        }
+        event.type_val.extend(types.iter().map(|t| t.to_string()));
+    }
+    event.outcome = match failed {
+        true => Some("failure".to_string()),
+        false => Some("success".to_string()),
+    };
+
+    event.action = Some(value);
+}
+
+/// Admin events have an operation type instead of an event type.
+fn convert_operation_type(json: &mut FluentBitJson, value: String) {
+    let event = json.event();
+
     match value.as_str() {
-        "LOGIN_ERROR" => {
-            event.category.push("authentication".to_string());
-            event.outcome = Some("failure".to_string());
-        },
-        "LOGIN" => {
-            event.category.push("authentication".to_string());
-            event.outcome = Some("success".to_string());
-        },
-        // "CODE_TO_TOKEN" => {
-        //     event.category.push("authentication".to_string());
-        // }
+        "CREATE" => event.type_val.push("creation".to_string()),
+        "UPDATE" | "ACTION" => event.type_val.push("change".to_string()),
+        "DELETE" => event.type_val.push("deletion".to_string()),
         _ => {}
     }
-    
+    // Failed admin events are logged with an additional error key.
+    event.outcome.get_or_insert_with(|| "success".to_string());
+
     event.action = Some(value);
 }
 
+/// Adds the ECS type of the resource an admin event operated on. Users and groups have their own
+/// types, `admin` is for other IAM resources like roles, client scopes and permissions.
+// https://www.keycloak.org/docs-api/latest/javadocs/org/keycloak/events/admin/ResourceType.html
+fn convert_resource_type(json: &mut FluentBitJson, resource_type: &str) {
+    let event = json.event();
+
+    let resource = match resource_type {
+        "USER"
+        | "USER_SESSION"
+        | "USER_LOGIN_FAILURE"
+        | "REALM_ROLE_MAPPING"
+        | "CLIENT_ROLE_MAPPING" => Some("user"),
+        "GROUP" | "GROUP_MEMBERSHIP" => Some("group"),
+        "REALM_ROLE" | "CLIENT_ROLE" | "CLIENT_SCOPE" => Some("admin"),
+        _ if resource_type.starts_with("AUTHORIZATION_") => Some("admin"),
+        _ => None,
+    };
+    match resource {
+        Some(resource) => event.type_val.push(resource.to_string()),
+        None => {
+            if !event.category.iter().any(|c| c == "configuration") {
+                event.category.push("configuration".to_string());
+            }
+        }
+    }
+}
+
+/// The ECS category and types of a Keycloak user event type without the `_ERROR` suffix.
+// https://www.keycloak.org/docs-api/latest/javadocs/org/keycloak/events/EventType.html
+fn convert_event_taxonomy(event_type: &str) -> Option<(&'static str, &'static [&'static str])> {
+    match event_type {
+        "LOGIN"
+        | "CLIENT_LOGIN"
+        | "IDENTITY_PROVIDER_LOGIN"
+        | "IDENTITY_PROVIDER_FIRST_LOGIN"
+        | "IDENTITY_PROVIDER_POST_LOGIN"
+        | "IMPERSONATE" => Some(("authentication", &["start"])),
+        "LOGOUT" => Some(("authentication", &["end"])),
+        "CODE_TO_TOKEN"
+        | "REFRESH_TOKEN"
+        | "TOKEN_EXCHANGE"
+        | "INTROSPECT_TOKEN"
+        | "USER_INFO_REQUEST"
+        | "IDENTITY_PROVIDER_RETRIEVE_TOKEN" => Some(("authentication", &["info"])),
+        "REGISTER" => Some(("iam", &["user", "creation"])),
+        "UPDATE_PASSWORD"
+        | "UPDATE_TOTP"
+        | "REMOVE_TOTP"
+        | "UPDATE_PROFILE"
+        | "UPDATE_EMAIL"
+        | "VERIFY_EMAIL"
+        | "RESET_PASSWORD"
+        | "UPDATE_CREDENTIAL"
+        | "REMOVE_CREDENTIAL"
+        | "FEDERATED_IDENTITY_LINK"
+        | "REMOVE_FEDERATED_IDENTITY"
+        | "GRANT_CONSENT"
+        | "UPDATE_CONSENT"
+        | "REVOKE_GRANT" => Some(("iam", &["user", "change"])),
+        "SEND_RESET_PASSWORD" | "SEND_VERIFY_EMAIL" | "SEND_IDENTITY_PROVIDER_LINK" => {
+            Some(("iam", &["user", "info"]))
+        }
+        "DELETE_ACCOUNT" => Some(("iam", &["user", "deletion"])),
+        _ => None,
+    }
+}
+
+/// Converts the camel case keys of Keycloak, e.g. `authSessionParentId`, to snake case.
+fn snake_case(key: &str) -> String {
+    let mut snake_case = String::with_capacity(key.len());
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            if !snake_case.is_empty() {
+                snake_case.push('_');
+            }
+            snake_case.push(c.to_ascii_lowercase());
+        } else {
+            snake_case.push(c);
+        }
+    }
+    snake_case
+}
+
+/// Whether the string has the form of a UUID, e.g. `4d1a7b3e-56b2-4c1e-8f0a-9d3c2b1a0e7f`.
+fn is_uuid(string: &str) -> bool {
+    string.len() == 36
+        && string.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
 fn unmask(string: &str) -> String {
     string
         .replace("\\\\", "\\")
@@ -0,0 +1,118 @@
+use chrono::{DateTime, FixedOffset, NaiveTime};
+
+use pest::Parser;
+use pest_derive::Parser;
+
+use crate::config::Config;
+use crate::converter::{Context, LogConverter, Outcome};
+use crate::model::{FluentBitJson, LogOrString};
+use crate::timestamp;
+
+// https://github.com/kubernetes/klog/blob/main/klog.go
+// Lmmdd hh:mm:ss.uuuuuu threadid file:line] msg...
+#[derive(Parser)]
+#[grammar_inline = r#"
+klog_log = { SOI ~ level ~ month ~ day ~ " " ~ hour ~ ":" ~ minute ~ ":" ~ second ~ "." ~ micros ~ " "+ ~ thread_id ~ " " ~ file ~ ":" ~ line ~ "] " ~ message ~ EOI }
+level = { "I" | "W" | "E" | "F" }
+month = { ASCII_DIGIT{2} }
+day = { ASCII_DIGIT{2} }
+hour = { ASCII_DIGIT{2} }
+minute = { ASCII_DIGIT{2} }
+second = { ASCII_DIGIT{2} }
+micros = { ASCII_DIGIT{6} }
+thread_id = { ASCII_DIGIT+ }
+file = { (!(":" | " " | "]") ~ ANY)+ }
+line = { ASCII_DIGIT+ }
+message = { ANY* }
+"#]
+struct KlogParser;
+
+pub struct KlogConverter;
+
+impl LogConverter for KlogConverter {
+    fn name(&self) -> &str {
+        "klog"
+    }
+
+    fn convert(&self, json: &mut FluentBitJson, context: &Context) -> Outcome {
+        convert_klog_logs(json, context.event_date, context.config)
+    }
+}
+
+/// Whether the log line starts with a klog header.
+pub fn is_klog_log(log: &str) -> bool {
+    KlogParser::parse(Rule::klog_log, log).is_ok()
+}
+
+fn convert_klog_logs(
+    json: &mut FluentBitJson,
+    event_date: &DateTime<FixedOffset>,
+    config: &Config,
+) -> Outcome {
+    let log = match json.log.as_ref() {
+        Some(LogOrString::String(log)) => log.clone(),
+        _ => return Outcome::Fallback,
+    };
+    let pairs = match KlogParser::parse(Rule::klog_log, &log) {
+        Ok(pairs) => pairs,
+        Err(_) => return Outcome::Fallback,
+    };
+    json.log = None;
+    let timezone = timestamp::timezone(json, config);
+
+    json.event().module = Some("klog".to_string());
+
+    let mut month: u32 = 0;
+    let mut day: u32 = 0;
+    let mut hour: u32 = 1000;
+    let mut minute: u32 = 1000;
+    let mut second: u32 = 1000;
+    let mut micros: u32 = 0;
+
+    for pair in pairs.flat_map(|pair| pair.into_inner()) {
+        match pair.as_rule() {
+            Rule::level => {
+                let level = convert_level(pair.as_str());
+                json.event().severity = config
+                    .severity("klog", level)
+                    .or_else(|| convert_severity(level));
+                json.log().level = Some(level.to_string());
+            }
+            Rule::month => month = pair.as_str().parse().unwrap_or(0),
+            Rule::day => day = pair.as_str().parse().unwrap_or(0),
+            Rule::hour => hour = pair.as_str().parse().unwrap_or(1000),
+            Rule::minute => minute = pair.as_str().parse().unwrap_or(1000),
+            Rule::second => second = pair.as_str().parse().unwrap_or(1000),
+            Rule::micros => micros = pair.as_str().parse().unwrap_or(0),
+            Rule::thread_id => json.process().thread().id = pair.as_str().parse().ok(),
+            Rule::file => json.log().origin().file().name = Some(pair.as_str().to_string()),
+            Rule::line => json.log().origin().file().line = pair.as_str().parse().ok(),
+            Rule::message => json.message = Some(pair.as_str().to_string()),
+            _ => {}
+        }
+    }
+
+    json.timestamp = NaiveTime::from_hms_micro_opt(hour, minute, second, micros)
+        .and_then(|time| timestamp::complete_date(month, day, time, event_date, timezone));
+
+    Outcome::Full
+}
+
+fn convert_level(level: &str) -> &'static str {
+    match level {
+        "W" => "warning",
+        "E" => "error",
+        "F" => "fatal",
+        _ => "info",
+    }
+}
+
+fn convert_severity(level: &str) -> Option<u32> {
+    match level {
+        "info" => Some(200),
+        "warning" => Some(300),
+        "error" => Some(400),
+        "fatal" => Some(500),
+        _ => None,
+    }
+}
@@ -55,10 +55,7 @@ fn convert(kubernetes: Kubernetes, json: &mut FluentBitJson) {
         json.container().image().name = Some(container_image);
     }
 
-    let container_hash = kubernetes
-        .container_hash
-        .map(extract_container_hash)
-        .flatten();
+    let container_hash = kubernetes.container_hash.and_then(extract_container_hash);
     if let Some(hash) = container_hash {
         json.container().image().hash().all = vec![hash]
     }
@@ -72,8 +69,8 @@ fn extract_container_hash(hash: String) -> Option<String> {
     parts.last().map(|last_part| last_part.to_string())
 }
 
-fn detect_resource_parent(label: &Vec<String>) -> Option<String> {
-    label.into_iter().find_map(|candidate| {
+fn detect_resource_parent(label: &[String]) -> Option<String> {
+    label.iter().find_map(|candidate| {
         if candidate.starts_with("statefulset.kubernetes.io/pod-name:") {
             Some("StatefulSet".to_string())
         } else {
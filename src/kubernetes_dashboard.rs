@@ -1,9 +1,24 @@
 use chrono::{DateTime, FixedOffset};
 use serde_json::Value;
 
+use crate::config::Config;
+use crate::converter::{Context, LogConverter, Outcome};
 use crate::model::FluentBitJson;
 
-pub fn convert_kubernetes_dashboard_metrics_scraper(json: &mut FluentBitJson) {
+pub struct MetricsScraperConverter;
+
+impl LogConverter for MetricsScraperConverter {
+    fn name(&self) -> &str {
+        "kubernetes-dashboard-metrics-scraper"
+    }
+
+    fn convert(&self, json: &mut FluentBitJson, context: &Context) -> Outcome {
+        convert_kubernetes_dashboard_metrics_scraper(json, context.config);
+        Outcome::Full
+    }
+}
+
+fn convert_kubernetes_dashboard_metrics_scraper(json: &mut FluentBitJson, config: &Config) {
     let level = json.other.remove("level").and_then(|level| match level {
         Value::String(level) => Some(level),
         _ => None,
@@ -12,7 +27,7 @@ pub fn convert_kubernetes_dashboard_metrics_scraper(json: &mut FluentBitJson) {
     // @timestamp
     match convert_ts(json) {
         TsParseResult::Ok(ts) => json.timestamp = Some(ts),
-        TsParseResult::Err(ts) => json.misc.push(format!("time:{}", ts)),
+        TsParseResult::Err(ts) => json.add_non_ecs("time", ts),
         TsParseResult::None => {}
     }
 
@@ -26,18 +41,16 @@ pub fn convert_kubernetes_dashboard_metrics_scraper(json: &mut FluentBitJson) {
     // event
     let event = json.event();
     event.module = Some("kubernetes_dashboard".to_string());
-    match &level {
-        Some(level) => {
-            event.severity = convert_severity(&level);
-        }
-        _ => {}
+    if let Some(level) = &level {
+        event.severity = config
+            .severity("kubernetes-dashboard-metrics-scraper", level)
+            .or_else(|| convert_severity(level));
     };
 
     // log
     let log = json.log();
-    match level {
-        Some(level) => log.level = Some(level),
-        _ => {}
+    if let Some(level) = level {
+        log.level = Some(level)
     };
 }
 
@@ -65,4 +78,4 @@ enum TsParseResult {
     Ok(DateTime<FixedOffset>),
     Err(String),
     None,
-}
\ No newline at end of file
+}
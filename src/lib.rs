@@ -1,118 +1,473 @@
+use std::any::Any;
+use std::cell::RefCell;
 use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
 use std::slice;
 
 use chrono::{DateTime, Duration, FixedOffset};
+use config::Config;
+use converter::{Context, Outcome};
+use log::warn;
 use model::LogOrString;
 use serde_json::Value;
 
+pub mod config;
+pub mod converter;
 mod kubernetes;
-mod model;
+#[allow(clippy::new_without_default)]
+pub mod model;
 // app log parsers
 mod etcd;
 mod keycloak;
 mod kubernetes_dashboard;
 mod metallb;
 mod postfix;
+// generic log format parsers
+mod java;
+mod klog;
+mod logfmt;
+mod sniff;
+mod timestamp;
 
+thread_local! {
+    /// Holds the result of the last `fluent_ecs_filter` or `fluent_ecs_filter_msgpack` call.
+    ///
+    /// fluent-bit copies the returned string out of the WASM memory before it calls the filter
+    /// again and never frees it. Reusing one module-level buffer keeps the returned pointer valid
+    /// until the next call without leaking memory.
+    static RESULT_BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The filter function called by fluent-bit for records in JSON format.
+///
+/// Returns a pointer to a NUL-terminated JSON string. The string is owned by fluent-ecs and stays
+/// valid until the next call of this function.
 #[no_mangle]
 pub extern "C" fn fluent_ecs_filter(
-    _tag: *const c_char,
-    _tag_len: u32,
+    tag: *const c_char,
+    tag_len: u32,
     time_sec: u32,
     time_nsec: u32,
     record: *const c_char,
     record_len: u32,
 ) -> *const u8 {
-    let slice_record: &[u8] =
-        unsafe { slice::from_raw_parts(record as *const u8, record_len as usize) };
+    call_filter(
+        tag,
+        tag_len,
+        time_sec,
+        time_nsec,
+        record,
+        record_len,
+        |tag, record, time| fluent_ecs_filter_with_tag(tag, record, time).into_bytes(),
+        b"{\"event\":{\"kind\":\"pipeline_error\",\"module\":\"fluent-ecs\"}}",
+    )
+}
+
+/// The filter function called by fluent-bit with `Event_Format msgpack`.
+///
+/// fluent-bit passes the record MessagePack encoded but still expects a JSON string as result.
+/// Like [fluent_ecs_filter] this returns a pointer to a NUL-terminated JSON string that stays valid
+/// until the next call of a filter function.
+#[no_mangle]
+pub extern "C" fn fluent_ecs_filter_msgpack(
+    tag: *const c_char,
+    tag_len: u32,
+    time_sec: u32,
+    time_nsec: u32,
+    record: *const c_char,
+    record_len: u32,
+) -> *const u8 {
+    call_filter(
+        tag,
+        tag_len,
+        time_sec,
+        time_nsec,
+        record,
+        record_len,
+        |tag, record, time| fluent_ecs_filter_msgpack_rust(tag, record, time).into_bytes(),
+        b"{\"event\":{\"kind\":\"pipeline_error\",\"module\":\"fluent-ecs\"}}",
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn call_filter(
+    tag: *const c_char,
+    tag_len: u32,
+    time_sec: u32,
+    time_nsec: u32,
+    record: *const c_char,
+    record_len: u32,
+    filter: impl Fn(&str, &[u8], DateTime<FixedOffset>) -> Vec<u8>,
+    panic_result: &[u8],
+) -> *const u8 {
+    let slice_tag: &[u8] = if tag.is_null() {
+        &[]
+    } else {
+        unsafe { slice::from_raw_parts(tag as *const u8, tag_len as usize) }
+    };
+    let slice_record: &[u8] = if record.is_null() {
+        &[]
+    } else {
+        unsafe { slice::from_raw_parts(record as *const u8, record_len as usize) }
+    };
 
+    // An invalid nanosecond part must not prevent the record from being processed.
     let time = DateTime::from_timestamp(time_sec.into(), time_nsec)
-        .expect("Time passed from fluent-bit could not be parsed.")
+        .or_else(|| DateTime::from_timestamp(time_sec.into(), 0))
+        .unwrap_or_default()
         .fixed_offset();
-    let mut res = fluent_ecs_filter_rust(slice_record, time)
-        .as_bytes()
-        .to_vec();
-    res.push(0);
-    res.as_ptr()
+
+    // Unwinding across the FFI boundary is undefined behavior. This only guards native builds of
+    // the library, panics abort on the wasm32-unknown-unknown target used by fluent-bit.
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        filter(&String::from_utf8_lossy(slice_tag), slice_record, time)
+    }))
+    .unwrap_or_else(|_| panic_result.to_vec());
+
+    RESULT_BUFFER.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.clear();
+        buffer.extend_from_slice(&res);
+        buffer.push(0);
+        buffer.as_ptr()
+    })
 }
 
 // https://www.elastic.co/guide/en/ecs/current/ecs-ecs.html
 // ecs.version: 8.11
 
+/// Converts a fluent-bit record without tag to ECS using the configuration loaded from
+/// [config::global].
 pub fn fluent_ecs_filter_rust(record: &[u8], time: DateTime<FixedOffset>) -> String {
-    let mut json: model::FluentBitJson = serde_json::from_slice(record).unwrap();
+    fluent_ecs_filter_with_tag("", record, time)
+}
 
-    do_app_specific_conversion(&mut json, &time);
+/// Converts a fluent-bit record to ECS using the configuration loaded from [config::global].
+pub fn fluent_ecs_filter_with_tag(tag: &str, record: &[u8], time: DateTime<FixedOffset>) -> String {
+    fluent_ecs_filter_with_config(config::global(), tag, record, time)
+}
 
-    kubernetes::convert_kubernetes_metadata(&mut json);
+/// Converts a fluent-bit record to ECS using the passed configuration.
+pub fn fluent_ecs_filter_with_config(
+    config: &Config,
+    tag: &str,
+    record: &[u8],
+    time: DateTime<FixedOffset>,
+) -> String {
+    let parsed = serde_json::from_slice::<model::FluentBitJson>(record).map_err(|e| e.to_string());
+    let original = || String::from_utf8_lossy(record).into_owned();
+    let json = convert_record(parsed, config, tag, &original, time);
+
+    to_json(json, config, tag, &original, time)
+}
+
+/// Converts a MessagePack encoded fluent-bit record to an ECS record in JSON format using the
+/// configuration loaded from [config::global].
+pub fn fluent_ecs_filter_msgpack_rust(
+    tag: &str,
+    record: &[u8],
+    time: DateTime<FixedOffset>,
+) -> String {
+    fluent_ecs_filter_msgpack_with_config(config::global(), tag, record, time)
+}
+
+/// Converts a MessagePack encoded fluent-bit record to an ECS record in JSON format using the
+/// passed configuration. The result is identical to [fluent_ecs_filter_with_config].
+pub fn fluent_ecs_filter_msgpack_with_config(
+    config: &Config,
+    tag: &str,
+    record: &[u8],
+    time: DateTime<FixedOffset>,
+) -> String {
+    let parsed = rmp_serde::from_slice::<model::FluentBitJson>(record).map_err(|e| e.to_string());
+    let original = || msgpack_original(record);
+    let json = convert_record(parsed, config, tag, &original, time);
 
-    set_basic_data(&mut json, time);
+    to_json(json, config, tag, &original, time)
+}
 
+fn to_json(
+    json: model::FluentBitJson,
+    config: &Config,
+    tag: &str,
+    original: &dyn Fn() -> String,
+    time: DateTime<FixedOffset>,
+) -> String {
     match serde_json::to_string(&json) {
         Ok(res) => res,
-        Err(err) => format!("{{\"fluent-ecs-error\": \"{}\"}}", err),
+        Err(err) => serde_json::to_string(&convert_pipeline_error(
+            original,
+            tag,
+            format!("fluent-ecs could not serialize the record: {}", err),
+            time,
+            config,
+        ))
+        .unwrap_or_default(),
     }
 }
 
-fn do_app_specific_conversion(json: &mut model::FluentBitJson, event_date: &DateTime<FixedOffset>) {
-    if let Some(Value::String(parser)) = json
-        .kubernetes
-        .as_ref()
-        .and_then(|k| k.annotations.get("fluent-ecs.bieniek-it.de/parser"))
-    {
-        if try_app_specific_conversion(parser.clone().as_str(), json, &event_date) {
-            return;
+/// Renders a MessagePack record as JSON for `event.original` so it stays readable.
+fn msgpack_original(record: &[u8]) -> String {
+    match rmp_serde::from_slice::<Value>(record) {
+        Ok(value) => value.to_string(),
+        Err(_) => String::from_utf8_lossy(record).into_owned(),
+    }
+}
+
+fn convert_record(
+    parsed: Result<model::FluentBitJson, String>,
+    config: &Config,
+    tag: &str,
+    original: &dyn Fn() -> String,
+    time: DateTime<FixedOffset>,
+) -> model::FluentBitJson {
+    match parsed {
+        // Only native builds like the command line tool can catch panics, WASM builds abort.
+        Ok(json) => panic::catch_unwind(AssertUnwindSafe(|| convert(json, tag, time, config)))
+            .unwrap_or_else(|panic| {
+                let reason = panic_reason(panic.as_ref());
+                convert_pipeline_error(
+                    original,
+                    tag,
+                    format!("fluent-ecs failed to convert the record: {}", reason),
+                    time,
+                    config,
+                )
+            }),
+        Err(err) => convert_pipeline_error(
+            original,
+            tag,
+            format!("fluent-ecs could not parse the record: {}", err),
+            time,
+            config,
+        ),
+    }
+}
+
+fn convert(
+    mut json: model::FluentBitJson,
+    tag: &str,
+    time: DateTime<FixedOffset>,
+    config: &Config,
+) -> model::FluentBitJson {
+    json.merge_input_labels();
+
+    let metadata = do_app_specific_conversion(&mut json, tag, &time, config);
+    let parser = metadata.as_ref().map(|metadata| metadata.parser.clone());
+    if config.output.pipeline_metadata {
+        if let Some(metadata) = metadata {
+            set_pipeline_metadata(&mut json, metadata);
         }
     }
 
-    if let Some(Value::String(app)) = json
-        .kubernetes
-        .as_ref()
-        .and_then(|k| k.labels.get("app.kubernetes.io/name"))
-    {
-        if try_app_specific_conversion(app.clone().as_str(), json, &event_date) {
-            return;
+    kubernetes::convert_kubernetes_metadata(&mut json);
+
+    set_basic_data(&mut json, tag, time, config);
+    set_related(&mut json);
+    json.apply_non_ecs_policy(config.non_ecs_policy(parser.as_deref()));
+
+    json
+}
+
+/// Creates a valid ECS record for input that could not be converted, preserving the raw input.
+fn convert_pipeline_error(
+    original: &dyn Fn() -> String,
+    tag: &str,
+    message: String,
+    time: DateTime<FixedOffset>,
+    config: &Config,
+) -> model::FluentBitJson {
+    warn!("{}", message);
+
+    let mut json = model::FluentBitJson::new();
+    json.error().message = Some(message);
+
+    let event = json.event();
+    event.kind = Some("pipeline_error".to_string());
+    event.severity = Some(300);
+    event.outcome = Some("failure".to_string());
+    event.original = Some(original());
+
+    set_basic_data(&mut json, tag, time, config);
+    json.apply_non_ecs_policy(config.non_ecs_policy(None));
+    json
+}
+
+fn panic_reason(panic: &(dyn Any + Send)) -> &str {
+    if let Some(reason) = panic.downcast_ref::<&str>() {
+        reason
+    } else if let Some(reason) = panic.downcast_ref::<String>() {
+        reason
+    } else {
+        "unknown panic"
+    }
+}
+
+/// Which converter handled a record, how it was selected and how much of the record it understood.
+struct PipelineMetadata {
+    parser: String,
+    detection: &'static str,
+    outcome: Outcome,
+}
+
+fn do_app_specific_conversion(
+    json: &mut model::FluentBitJson,
+    tag: &str,
+    event_date: &DateTime<FixedOffset>,
+    config: &Config,
+) -> Option<PipelineMetadata> {
+    let detection = &config.parser_detection;
+    let context = Context {
+        event_date,
+        tag,
+        config,
+    };
+
+    for annotation in &detection.annotations {
+        if let Some(Value::String(parser)) = json
+            .kubernetes
+            .as_ref()
+            .and_then(|k| k.annotations.get(annotation))
+        {
+            if let Some(metadata) =
+                try_app_specific_conversion(parser.clone().as_str(), json, &context, "annotation")
+            {
+                return Some(metadata);
+            }
         }
     }
 
-    if let Some(Value::String(component)) = json
-        .kubernetes
-        .as_ref()
-        .and_then(|k| k.labels.get("component"))
-    {
-        try_app_specific_conversion(component.clone().as_str(), json, &event_date);
+    for label in &detection.labels {
+        if let Some(Value::String(app)) = json.kubernetes.as_ref().and_then(|k| k.labels.get(label))
+        {
+            let detection = if label == "component" {
+                "component"
+            } else {
+                "label"
+            };
+            if let Some(metadata) =
+                try_app_specific_conversion(app.clone().as_str(), json, &context, detection)
+            {
+                return Some(metadata);
+            }
+        }
+    }
+
+    // Inputs without Kubernetes metadata like tail, syslog or systemd can only be identified by their tag.
+    for tag_parser in &detection.tags {
+        if !tag_matches(&tag_parser.pattern, tag) {
+            continue;
+        }
+        if let Some(metadata) =
+            try_app_specific_conversion(&tag_parser.parser, json, &context, "tag")
+        {
+            return Some(metadata);
+        }
+    }
+
+    if detection.sniff {
+        if let Some(parser) = sniff::sniff_parser(json) {
+            if let Some(metadata) = try_app_specific_conversion(parser, json, &context, "sniff") {
+                return Some(metadata);
+            }
+        }
+    }
+
+    let parser = config.default_parser.as_ref()?;
+    try_app_specific_conversion(parser, json, &context, "default")
+}
+
+fn tag_matches(pattern: &str, tag: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == tag,
+        Some((prefix, rest)) => match tag.strip_prefix(prefix) {
+            None => false,
+            Some(tag) => (0..=tag.len())
+                .filter(|i| tag.is_char_boundary(*i))
+                .any(|i| tag_matches(rest, &tag[i..])),
+        },
     }
 }
 
 fn try_app_specific_conversion(
     app: &str,
     json: &mut model::FluentBitJson,
-    event_date: &DateTime<FixedOffset>,
-) -> bool {
-    match app {
-        "metallb" => metallb::convert_metallb_logs(json),
-        "etcd" => etcd::convert_etcd_logs(json),
-        "postfix" => postfix::convert_postfix_logs(json, event_date),
-        "keycloak" => keycloak::convert_keycloak_logs(json),
-        "kubernetes-dashboard-metrics-scraper" => {
-            kubernetes_dashboard::convert_kubernetes_dashboard_metrics_scraper(json)
-        }
-        _ => {
-            return false;
-        }
-    };
-    true
+    context: &Context,
+    detection: &'static str,
+) -> Option<PipelineMetadata> {
+    let converter = converter::find_converter(app)?;
+    if !context.config.is_parser_enabled(converter.name()) {
+        return None;
+    }
+
+    let outcome = converter.convert(json, context);
+    Some(PipelineMetadata {
+        parser: converter.name().to_string(),
+        detection,
+        outcome,
+    })
 }
 
-fn set_basic_data(json: &mut model::FluentBitJson, time: DateTime<FixedOffset>) {
+/// Collects the values to pivot on from the fields set by the converters.
+fn set_related(json: &mut model::FluentBitJson) {
+    let mut ips = Vec::new();
+    let mut hosts = Vec::new();
+    for endpoint in [&json.source, &json.destination].into_iter().flatten() {
+        ips.extend(endpoint.ip.clone());
+        hosts.extend(endpoint.domain.clone());
+    }
+    hosts.extend(json.host.as_ref().and_then(|host| host.hostname.clone()));
+    let users: Vec<String> = json
+        .user
+        .iter()
+        .flat_map(|user| user.name.clone())
+        .collect();
+    let hashes: Vec<String> = json
+        .container
+        .iter()
+        .flat_map(|container| container.image.as_ref()?.hash.as_ref())
+        .flat_map(|hash| hash.all.clone())
+        .collect();
+
+    if ips.is_empty() && hosts.is_empty() && users.is_empty() && hashes.is_empty() {
+        return;
+    }
+    let related = json.related();
+    ips.iter().for_each(|ip| related.add_ip(ip));
+    hosts.iter().for_each(|host| related.add_host(host));
+    users.iter().for_each(|user| related.add_user(user));
+    hashes.iter().for_each(|hash| related.add_hash(hash));
+}
+
+fn set_pipeline_metadata(json: &mut model::FluentBitJson, metadata: PipelineMetadata) {
+    json.labels.insert(
+        "fluent_ecs_parser".to_string(),
+        Value::String(metadata.parser),
+    );
+    json.labels.insert(
+        "fluent_ecs_detection".to_string(),
+        Value::String(metadata.detection.to_string()),
+    );
+    json.labels.insert(
+        "fluent_ecs_outcome".to_string(),
+        Value::String(metadata.outcome.as_str().to_string()),
+    );
+}
+
+fn set_basic_data(
+    json: &mut model::FluentBitJson,
+    tag: &str,
+    time: DateTime<FixedOffset>,
+    config: &Config,
+) {
     // event
     {
         let stream = json.other.remove("stream");
         let event = json.event();
-        if event.kind == None {
+        if event.kind.is_none() {
             event.kind.get_or_insert("event".to_string());
         }
-        if event.module == None {
+        if event.module.is_none() {
             event.module = Some("fluent-ecs".to_string());
             if let (None, Some(Value::String(stream))) = (event.dataset.as_ref(), stream) {
                 event.dataset = Some(format!("fluent-ecs.{}", stream));
@@ -131,20 +486,24 @@ fn set_basic_data(json: &mut model::FluentBitJson, time: DateTime<FixedOffset>)
     json.other.remove("time"); // This should be the same as the time passed via method arguments.
 
     // log to message
-    match (json.log.as_ref(), json.message.as_ref()) {
-        (Some(LogOrString::String(log_string)), Some(_)) => {
-            json.misc.push(format!("log:{}", log_string.to_string()));
-            json.log = None;
-        }
-        (Some(LogOrString::String(log_string)), None) => {
-            json.message = Some(log_string.to_string());
-            json.log = None;
+    if let Some(LogOrString::String(_)) = json.log {
+        if let Some(LogOrString::String(log_string)) = json.log.take() {
+            if json.message.is_some() {
+                json.add_non_ecs("log", log_string);
+            } else {
+                json.message = Some(log_string);
+            }
         }
-        _ => {}
     }
 
     // fluent-bit processing internals
     json.other.remove("_p");
+
+    // fluent-bit tag
+    if config.output.fluentbit_tag && !tag.is_empty() {
+        json.labels
+            .insert("fluentbit_tag".to_string(), Value::String(tag.to_string()));
+    }
 }
 
 #[cfg(test)]
@@ -153,7 +512,7 @@ mod tests {
     use log::info;
     use rstest::*;
     use serde_json::Value;
-    use std::{fs, sync::Once};
+    use std::{ffi::CStr, fs, sync::Once};
 
     use super::*;
 
@@ -170,6 +529,10 @@ mod tests {
 
     #[rstest]
     #[case::generic_tail_input("generic_tail_input")]
+    #[case::generic_http_fields("generic_http_fields")]
+    #[case::generic_input_labels("generic_input_labels")]
+    #[case::generic_input_labels_string("generic_input_labels_string")]
+    #[case::generic_input_related("generic_input_related")]
     #[case::kubernetes_statefulset("kubernetes_statefulset")]
     #[case::kubernetes_deployment("kubernetes_deployment")]
     #[case::etcd_took("etcd_took")]
@@ -180,6 +543,7 @@ mod tests {
     #[case::metallb_speaker_partial_join("metallb/speaker_partial_join")]
     #[case::metallb_controller_poolreconciler("metallb/controller_poolreconciler")]
     #[case::metallb_controller_cert_rotation("metallb/controller_cert_rotation")]
+    #[case::metallb_speaker_duplicate_ips("metallb/speaker_duplicate_ips")]
     // Postfix
     #[case::postfix_parse_error("postfix/parse_error")]
     #[case::postfix_smtpd_connect_from_unknown("postfix/smtpd_connect_from_unknown")]
@@ -194,6 +558,8 @@ mod tests {
     #[case::anvil_stats_count("postfix/anvil_stats_count")]
     #[case::anvil_stats_cache("postfix/anvil_stats_cache")]
     #[case::smtpd_auth_failed("postfix/smtpd_auth_failed")]
+    #[case::smtpd_auth_failed_reason("postfix/smtpd_auth_failed_reason")]
+    #[case::smtpd_auth_failed_username("postfix/smtpd_auth_failed_username")]
     #[case::smtpd_auth("postfix/smtpd_auth")]
     #[case::smtpd_non_auth("postfix/smtpd_non_auth")]
     #[case::qmgr_from("postfix/qmgr_from")]
@@ -202,26 +568,185 @@ mod tests {
     #[case::smtp_transfer("postfix/smtp_transfer")]
     #[case::smtp_transfer_deferred("postfix/smtp_transfer_deferred")]
     #[case::lmtp_transfer("postfix/lmtp_transfer")]
+    #[case::smtpd_reject_relay("postfix/smtpd_reject_relay")]
+    #[case::smtpd_reject_client_host("postfix/smtpd_reject_client_host")]
+    #[case::smtpd_tls_established("postfix/smtpd_tls_established")]
+    #[case::smtp_tls_established("postfix/smtp_tls_established")]
+    #[case::smtp_tls_peer_certificate("postfix/smtp_tls_peer_certificate")]
+    #[case::lmtp_tls_established("postfix/lmtp_tls_established")]
+    #[case::smtpd_reject_connect("postfix/smtpd_reject_connect")]
+    #[case::cleanup_milter_reject("postfix/cleanup_milter_reject")]
+    #[case::cleanup_header_reject("postfix/cleanup_header_reject")]
+    #[case::cleanup_header_hold("postfix/cleanup_header_hold")]
+    #[case::cleanup_header_hold_local("postfix/cleanup_header_hold_local")]
+    #[case::cleanup_body_discard("postfix/cleanup_body_discard")]
+    #[case::smtp_connection_refused("postfix/smtp_connection_refused")]
+    #[case::timestamp_rfc3339("postfix/timestamp_rfc3339")]
+    #[case::syslog_rfc5424("postfix/syslog_rfc5424")]
+    #[case::syslog_rfc5424_nil("postfix/syslog_rfc5424_nil")]
+    #[case::timezone_annotation("postfix/timezone_annotation")]
+    #[case::local_delivery("postfix/local_delivery")]
+    #[case::virtual_delivery("postfix/virtual_delivery")]
+    #[case::pipe_delivery("postfix/pipe_delivery")]
+    #[case::bounce_non_delivery("postfix/bounce_non_delivery")]
+    #[case::pickup("postfix/pickup")]
+    #[case::postscreen_connect("postfix/postscreen_connect")]
+    #[case::postscreen_pass_new("postfix/postscreen_pass_new")]
+    #[case::postscreen_pass_old("postfix/postscreen_pass_old")]
+    #[case::postscreen_dnsbl_rank("postfix/postscreen_dnsbl_rank")]
+    #[case::postscreen_pregreet("postfix/postscreen_pregreet")]
+    #[case::postscreen_hangup("postfix/postscreen_hangup")]
+    #[case::postscreen_disconnect("postfix/postscreen_disconnect")]
+    #[case::postscreen_reject("postfix/postscreen_reject")]
+    #[case::dnsblog_listed("postfix/dnsblog_listed")]
     // Keycloak
     #[case::keycloak_msg_acr_loa_map("keycloak/msg_acr_loa_map")]
+    #[case::keycloak_msg_mdc("keycloak/msg_mdc")]
+    #[case::keycloak_msg_exception("keycloak/msg_exception")]
+    #[case::keycloak_msg_exception_stack_trace("keycloak/msg_exception_stack_trace")]
     #[case::keycloak_event_login_error_unkown_user("keycloak/event_login_error_unkown_user")]
     #[case::keycloak_event_login("keycloak/event_login")]
+    #[case::keycloak_event_login_realm_name("keycloak/event_login_realm_name")]
+    #[case::keycloak_event_login_input_url("keycloak/event_login_input_url")]
+    #[case::keycloak_event_login_input_namespace("keycloak/event_login_input_namespace")]
     #[case::keycloak_code_to_token("keycloak/event_code_to_token")]
-    #[case::keycloak_login_error_invalid_credentials("keycloak/event_login_error_invalid_credentials")]
+    #[case::keycloak_event_code_to_token_error("keycloak/event_code_to_token_error")]
+    #[case::keycloak_event_client_login("keycloak/event_client_login")]
+    #[case::keycloak_event_impersonate("keycloak/event_impersonate")]
+    #[case::keycloak_event_token_exchange("keycloak/event_token_exchange")]
+    #[case::keycloak_login_error_invalid_credentials(
+        "keycloak/event_login_error_invalid_credentials"
+    )]
+    #[case::keycloak_event_logout("keycloak/event_logout")]
+    #[case::keycloak_event_refresh_token_error("keycloak/event_refresh_token_error")]
+    #[case::keycloak_event_register("keycloak/event_register")]
+    #[case::keycloak_event_update_password("keycloak/event_update_password")]
+    #[case::keycloak_event_update_totp("keycloak/event_update_totp")]
+    #[case::keycloak_event_remove_totp("keycloak/event_remove_totp")]
+    #[case::keycloak_event_send_reset_password("keycloak/event_send_reset_password")]
+    #[case::keycloak_event_delete_account("keycloak/event_delete_account")]
+    #[case::keycloak_event_identity_provider_login("keycloak/event_identity_provider_login")]
+    #[case::keycloak_event_unknown_error("keycloak/event_unknown_error")]
+    #[case::keycloak_event_admin_create_user("keycloak/event_admin_create_user")]
+    #[case::keycloak_event_admin_update_client("keycloak/event_admin_update_client")]
+    #[case::keycloak_event_admin_create_realm_role("keycloak/event_admin_create_realm_role")]
+    #[case::keycloak_event_admin_delete_group_membership_error(
+        "keycloak/event_admin_delete_group_membership_error"
+    )]
+    #[case::keycloak_event_unparsable("keycloak/event_unparsable")]
+    // Pipeline errors
+    #[case::pipeline_error_invalid_json("pipeline_error/invalid_json")]
+    #[case::pipeline_error_unexpected_event_shape("pipeline_error/unexpected_event_shape")]
     fn conversion_test(#[case] test_case: &str) -> Result<(), String> {
+        assert_conversion(&Config::default(), "", test_case)
+    }
+
+    #[rstest]
+    #[case::postfix_tail("postfix.var.log.mail", "tag/postfix_tail")]
+    #[case::unknown_tag("host.sshd", "tag/unknown_tag")]
+    #[case::kubernetes_before_tag("etcd.var.log", "tag/kubernetes_before_tag")]
+    fn tag_conversion_test(#[case] tag: &str, #[case] test_case: &str) -> Result<(), String> {
+        assert_conversion(&Config::default(), tag, test_case)
+    }
+
+    #[rstest]
+    #[case::custom_detection("config/custom_detection")]
+    #[case::disabled_parser("config/disabled_parser")]
+    #[case::default_parser("config/default_parser")]
+    #[case::non_ecs_nest("config/non_ecs_nest")]
+    #[case::non_ecs_labels("config/non_ecs_labels")]
+    #[case::non_ecs_labels_structured("config/non_ecs_labels_structured")]
+    #[case::non_ecs_per_parser("config/non_ecs_per_parser")]
+    #[case::legacy_misc_drop("config/legacy_misc_drop")]
+    #[case::postfix_timezone("config/postfix_timezone")]
+    #[case::klog_timezone("config/klog_timezone")]
+    fn config_conversion_test(#[case] test_case: &str) -> Result<(), String> {
+        let config_file = fs::read(format!("examples/{}-config.json", test_case))
+            .map_err(|err| format!("Config file could not be read: {}", err))?;
+        let config = Config::from_json(&config_file)
+            .map_err(|err| format!("Config file is not valid: {}", err))?;
+
+        assert_conversion(&config, "kube.var.log.containers", test_case)
+    }
+
+    #[rstest]
+    #[case::etcd_zap("sniff/etcd_zap")]
+    #[case::keycloak_quarkus("sniff/keycloak_quarkus")]
+    #[case::postfix_syslog("sniff/postfix_syslog")]
+    #[case::klog_header("sniff/klog_header")]
+    #[case::logfmt_line("sniff/logfmt_line")]
+    #[case::no_match("sniff/no_match")]
+    fn sniff_conversion_test(#[case] test_case: &str) -> Result<(), String> {
+        let config = Config {
+            parser_detection: config::ParserDetection {
+                sniff: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_conversion(&config, "", test_case)
+    }
+
+    struct ShoutingConverter;
+
+    impl converter::LogConverter for ShoutingConverter {
+        fn name(&self) -> &str {
+            "shouting"
+        }
+
+        fn aliases(&self) -> &[&str] {
+            &["shouting-app"]
+        }
+
+        fn convert(
+            &self,
+            json: &mut model::FluentBitJson,
+            _context: &Context,
+        ) -> converter::Outcome {
+            if let Some(LogOrString::String(log)) = json.log.take() {
+                json.message = Some(log.to_uppercase());
+            }
+            json.event().module = Some(self.name().to_string());
+            converter::Outcome::Full
+        }
+    }
+
+    /// Removes the converter from the process-wide registry even if the test fails, so that it
+    /// does not affect other tests.
+    struct UnregisterOnDrop(&'static str);
+
+    impl Drop for UnregisterOnDrop {
+        fn drop(&mut self) {
+            converter::unregister_converter(self.0);
+        }
+    }
+
+    #[test]
+    fn registered_converter_test() -> Result<(), String> {
+        converter::register_converter(ShoutingConverter);
+        let unregister = UnregisterOnDrop("shouting");
+
+        assert_conversion(&Config::default(), "", "converter/registered_converter")?;
+
+        drop(unregister);
+        assert!(converter::find_converter("shouting").is_none());
+        Ok(())
+    }
+
+    fn assert_conversion(config: &Config, tag: &str, test_case: &str) -> Result<(), String> {
         init_logger();
 
         let some_time = DateTime::parse_from_rfc3339("2023-11-16T13:27:38.555+01:00")
             .map_err(|err| err.to_string())?;
         let input = fs::read(format!("examples/{}-in.json", test_case))
-            .map_err(|err| format!("Input file could not be read: {}", err.to_string()))?;
+            .map_err(|err| format!("Input file could not be read: {}", err))?;
 
-        let actual_string = fluent_ecs_filter_rust(&input, some_time);
+        let actual_string = fluent_ecs_filter_with_config(config, tag, &input, some_time);
         let actual: Value = serde_json::from_str(&actual_string).map_err(|err| {
             format!(
                 "Module under test did not return valid JSON error:\n{}\n\n value:\n{}",
-                err.to_string(),
-                actual_string
+                err, actual_string
             )
         })?;
 
@@ -237,4 +762,144 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn ffi_result_buffer_test() -> Result<(), String> {
+        init_logger();
+
+        let test_cases = [
+            "kubernetes_statefulset",
+            "postfix/smtpd_auth_failed",
+            "generic_tail_input",
+            "pipeline_error/invalid_json",
+            "metallb/speaker_partial_join",
+        ];
+        let (time_sec, time_nsec) = (1700137658, 555_000_000);
+        let time = DateTime::from_timestamp(time_sec.into(), time_nsec)
+            .ok_or("Invalid test time")?
+            .fixed_offset();
+
+        let inputs = test_cases
+            .iter()
+            .map(|test_case| fs::read(format!("examples/{}-in.json", test_case)))
+            .collect::<Result<Vec<Vec<u8>>, _>>()
+            .map_err(|err| format!("Input file could not be read: {}", err))?;
+        let expected: Vec<String> = inputs
+            .iter()
+            .map(|input| fluent_ecs_filter_with_tag("tag", input, time))
+            .collect();
+
+        for i in 0..1000 {
+            let input = &inputs[i % inputs.len()];
+            let result = fluent_ecs_filter(
+                "tag".as_ptr() as *const c_char,
+                3,
+                time_sec,
+                time_nsec,
+                input.as_ptr() as *const c_char,
+                input.len() as u32,
+            );
+
+            let actual = unsafe { CStr::from_ptr(result as *const c_char) }
+                .to_str()
+                .map_err(|err| format!("Result is not valid UTF-8: {}", err))?;
+            assert_eq!(actual, expected[i % inputs.len()], "iteration {}", i);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn msgpack_equivalence_test() -> Result<(), String> {
+        init_logger();
+
+        let time = DateTime::parse_from_rfc3339("2023-11-16T13:27:38.555+01:00")
+            .map_err(|err| err.to_string())?;
+        let config = Config::default();
+
+        let mut inputs = Vec::new();
+        collect_inputs("examples", &mut inputs)?;
+        assert!(!inputs.is_empty());
+
+        for path in inputs {
+            let input = fs::read(&path).map_err(|err| format!("{}: {}", path, err))?;
+            // Inputs that are no valid JSON cannot be represented in MessagePack.
+            let Ok(input_value) = serde_json::from_slice::<Value>(&input) else {
+                continue;
+            };
+            let msgpack_input = rmp_serde::to_vec_named(&input_value)
+                .map_err(|err| format!("{}: {}", path, err))?;
+
+            let json_output: Value =
+                serde_json::from_str(&fluent_ecs_filter_with_config(&config, "tag", &input, time))
+                    .map_err(|err| format!("{}: {}", path, err))?;
+            let msgpack_output: Value = serde_json::from_str(
+                &fluent_ecs_filter_msgpack_with_config(&config, "tag", &msgpack_input, time),
+            )
+            .map_err(|err| format!("{}: output of MessagePack input is invalid: {}", path, err))?;
+
+            info!("Comparing JSON and MessagePack output of '{}'", path);
+            let parse_error = json_output["error"]["message"]
+                .as_str()
+                .is_some_and(|message| message.starts_with("fluent-ecs could not parse"));
+            if parse_error {
+                // serde_json adds the position of the error to the message.
+                assert_eq!(msgpack_output["event"]["kind"], "pipeline_error");
+                let original = msgpack_output["event"]["original"]
+                    .as_str()
+                    .unwrap_or_default();
+                assert_json_eq!(
+                    serde_json::from_str::<Value>(original).map_err(|err| err.to_string())?,
+                    input_value
+                );
+                continue;
+            }
+            assert_json_eq!(msgpack_output, json_output);
+        }
+
+        Ok(())
+    }
+
+    fn collect_inputs(dir: &str, inputs: &mut Vec<String>) -> Result<(), String> {
+        for entry in fs::read_dir(dir).map_err(|err| err.to_string())? {
+            let path = entry.map_err(|err| err.to_string())?.path();
+            let path_str = path.to_string_lossy().to_string();
+            if path.is_dir() {
+                collect_inputs(&path_str, inputs)?;
+            } else if path_str.ends_with("-in.json") {
+                inputs.push(path_str);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn ffi_msgpack_test() -> Result<(), String> {
+        init_logger();
+
+        let input: Value = serde_json::from_slice(
+            &fs::read("examples/postfix/smtpd_auth-in.json").map_err(|err| err.to_string())?,
+        )
+        .map_err(|err| err.to_string())?;
+        let input = rmp_serde::to_vec_named(&input).map_err(|err| err.to_string())?;
+
+        for _ in 0..100 {
+            let result = fluent_ecs_filter_msgpack(
+                "tag".as_ptr() as *const c_char,
+                3,
+                1700137658,
+                555_000_000,
+                input.as_ptr() as *const c_char,
+                input.len() as u32,
+            );
+            let result = unsafe { CStr::from_ptr(result as *const c_char) }
+                .to_str()
+                .map_err(|err| format!("Result is not valid UTF-8: {}", err))?;
+
+            let output: Value = serde_json::from_str(result).map_err(|err| err.to_string())?;
+            assert_eq!(output["event"]["module"], "postfix");
+        }
+
+        Ok(())
+    }
 }
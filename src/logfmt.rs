@@ -0,0 +1,136 @@
+use chrono::DateTime;
+
+use pest::Parser;
+use pest_derive::Parser;
+
+use crate::config::Config;
+use crate::converter::{Context, LogConverter, Outcome};
+use crate::model::{FluentBitJson, LogOrString};
+
+// https://brandur.org/logfmt
+// At least two pairs are required so that a plain message containing a "=" is not mistaken.
+#[derive(Parser)]
+#[grammar_inline = r#"
+logfmt_log = { SOI ~ " "* ~ key_value_pair ~ (" "+ ~ key_value_pair)+ ~ " "* ~ EOI }
+key_value_pair = { key ~ "=" ~ value? }
+key = { (!("=" | " " | "\"") ~ ANY)+ }
+value = ${ "\"" ~ quoted_value ~ "\"" | plain_value }
+quoted_value = @{ (!("\"" | "\\") ~ ANY | "\\" ~ ANY)* }
+plain_value = @{ (!" " ~ ANY)+ }
+"#]
+struct LogfmtParser;
+
+pub struct LogfmtConverter;
+
+impl LogConverter for LogfmtConverter {
+    fn name(&self) -> &str {
+        "logfmt"
+    }
+
+    fn convert(&self, json: &mut FluentBitJson, context: &Context) -> Outcome {
+        convert_logfmt_logs(json, context.config)
+    }
+}
+
+/// Whether the log line consists of logfmt key value pairs.
+pub fn is_logfmt_log(log: &str) -> bool {
+    LogfmtParser::parse(Rule::logfmt_log, log).is_ok()
+}
+
+fn convert_logfmt_logs(json: &mut FluentBitJson, config: &Config) -> Outcome {
+    let log = match json.log.as_ref() {
+        Some(LogOrString::String(log)) => log.clone(),
+        _ => return Outcome::Fallback,
+    };
+    let pairs = match LogfmtParser::parse(Rule::logfmt_log, &log) {
+        Ok(pairs) => pairs,
+        Err(_) => return Outcome::Fallback,
+    };
+    json.log = None;
+
+    json.event().module = Some("logfmt".to_string());
+
+    for pair in pairs.flat_map(|pair| pair.into_inner()) {
+        if pair.as_rule() == Rule::key_value_pair {
+            if let Some((key, value)) = convert_key_value(pair.into_inner()) {
+                convert_field(json, key, value, config);
+            }
+        }
+    }
+
+    Outcome::Full
+}
+
+fn convert_key_value(pairs: pest::iterators::Pairs<'_, Rule>) -> Option<(&str, String)> {
+    let mut key = None;
+    let mut value = String::new();
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::key => key = Some(pair.as_str()),
+            Rule::value => {
+                for pair in pair.into_inner() {
+                    match pair.as_rule() {
+                        Rule::quoted_value => value = unmask(pair.as_str()),
+                        Rule::plain_value => value = pair.as_str().to_string(),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Some((key?, value))
+}
+
+fn convert_field(json: &mut FluentBitJson, key: &str, value: String, config: &Config) {
+    match key {
+        "level" | "lvl" => {
+            let level = value.to_lowercase();
+            json.event().severity = config
+                .severity("logfmt", &level)
+                .or_else(|| convert_severity(&level));
+            json.log().level = Some(level);
+        }
+        "ts" | "time" | "timestamp" => match DateTime::parse_from_rfc3339(&value) {
+            Ok(ts) => json.timestamp = Some(ts),
+            Err(_) => json.add_non_ecs(key, value),
+        },
+        "msg" | "message" => json.message = Some(value),
+        "err" | "error" => json.error().message = Some(value),
+        "logger" => json.log().logger = Some(value),
+        "caller" => match value
+            .split_once(':')
+            .and_then(|(file, line)| Some((file, line.parse::<u32>().ok()?)))
+        {
+            Some((file, line)) => {
+                let origin_file = json.log().origin().file();
+                origin_file.name = Some(file.to_string());
+                origin_file.line = Some(line);
+            }
+            None => json.add_non_ecs(key, value),
+        },
+        _ => json.add_non_ecs(key, value),
+    }
+}
+
+fn unmask(string: &str) -> String {
+    string
+        .replace("\\\\", "\\")
+        .replace("\\\"", "\"")
+        .replace("\\n", "\n")
+        .replace("\\t", "\t")
+}
+
+fn convert_severity(level: &str) -> Option<u32> {
+    match level {
+        "trace" => Some(50),
+        "debug" => Some(100),
+        "info" => Some(200),
+        "warn" | "warning" => Some(300),
+        "error" => Some(400),
+        "fatal" | "panic" | "critical" => Some(500),
+        _ => None,
+    }
+}
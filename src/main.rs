@@ -0,0 +1,200 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process::ExitCode;
+use std::time::SystemTime;
+
+use chrono::{DateTime, FixedOffset};
+use fluent_ecs::config::{Config, ParserDetection};
+use fluent_ecs::converter::find_converter;
+use fluent_ecs::fluent_ecs_filter_with_config;
+use serde_json::Value;
+
+const USAGE: &str = "Converts newline-delimited fluent-bit JSON records to ECS.
+
+Usage: fluent-ecs [OPTIONS] [FILE]...
+
+Reads from stdin if no file or '-' is passed and writes ECS NDJSON to stdout.
+
+Options:
+      --time <RFC3339>   Reference time of the fluent-bit event [default: now]
+      --tag <TAG>        fluent-bit tag of the records
+      --parser <NAME>    Use this parser instead of detecting it
+      --raw              Treat every line as a raw log line instead of a fluent-bit record
+      --config <PATH>    Path of the fluent-ecs configuration file
+      --pretty           Pretty-print the output
+  -h, --help             Print this help";
+
+struct Args {
+    time: DateTime<FixedOffset>,
+    tag: String,
+    parser: Option<String>,
+    raw: bool,
+    config: Option<String>,
+    pretty: bool,
+    files: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut config = match &args.config {
+        Some(path) => match std::fs::read(path) {
+            Ok(content) => match Config::from_json(&content) {
+                Ok(config) => config,
+                Err(err) => {
+                    eprintln!("Config '{}' is invalid: {}", path, err);
+                    return ExitCode::FAILURE;
+                }
+            },
+            Err(err) => {
+                eprintln!("Config '{}' could not be read: {}", path, err);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Config::load(),
+    };
+    if let Some(parser) = &args.parser {
+        config.parser_detection = ParserDetection {
+            annotations: Vec::new(),
+            labels: Vec::new(),
+            tags: Vec::new(),
+            sniff: false,
+        };
+        config.default_parser = Some(parser.to_string());
+    }
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    let result = if args.files.is_empty() {
+        convert_lines(io::stdin().lock(), &mut out, &args, &config)
+    } else {
+        args.files.iter().try_for_each(|file| {
+            if file == "-" {
+                return convert_lines(io::stdin().lock(), &mut out, &args, &config);
+            }
+            let reader = File::open(file)
+                .map_err(|err| format!("File '{}' could not be read: {}", file, err))?;
+            convert_lines(BufReader::new(reader), &mut out, &args, &config)
+        })
+    };
+
+    match result.and_then(|_| out.flush().map_err(|err| err.to_string())) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut time = None;
+    let mut tag = String::new();
+    let mut parser = None;
+    let mut raw = false;
+    let mut config = None;
+    let mut pretty = false;
+    let mut files = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--time" => {
+                let value = option_value(&mut args, &arg)?;
+                time = Some(
+                    DateTime::parse_from_rfc3339(&value)
+                        .map_err(|err| format!("Invalid time '{}': {}", value, err))?,
+                );
+            }
+            "--tag" => tag = option_value(&mut args, &arg)?,
+            "--parser" => {
+                let value = option_value(&mut args, &arg)?;
+                if find_converter(&value).is_none() {
+                    return Err(format!("Unknown parser '{}'", value));
+                }
+                parser = Some(value);
+            }
+            "--raw" => raw = true,
+            "--config" => config = Some(option_value(&mut args, &arg)?),
+            "--pretty" => pretty = true,
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("Unknown option '{}'", arg))
+            }
+            _ => files.push(arg),
+        }
+    }
+
+    Ok(Some(Args {
+        time: time.unwrap_or_else(now),
+        tag,
+        parser,
+        raw,
+        config,
+        pretty,
+        files,
+    }))
+}
+
+fn option_value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("Option '{}' requires a value", option))
+}
+
+fn now() -> DateTime<FixedOffset> {
+    let since_epoch = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    DateTime::from_timestamp(since_epoch.as_secs() as i64, since_epoch.subsec_nanos())
+        .unwrap_or_default()
+        .fixed_offset()
+}
+
+fn convert_lines(
+    input: impl BufRead,
+    out: &mut impl Write,
+    args: &Args,
+    config: &Config,
+) -> Result<(), String> {
+    // Lines are not required to be valid UTF-8, the filter turns invalid records into pipeline
+    // errors instead of aborting the whole input.
+    for line in input.split(b'\n') {
+        let mut line = line.map_err(|err| format!("Input could not be read: {}", err))?;
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+
+        let record = if args.raw {
+            serde_json::json!({ "log": String::from_utf8_lossy(&line) })
+                .to_string()
+                .into_bytes()
+        } else {
+            line
+        };
+
+        let converted = fluent_ecs_filter_with_config(config, &args.tag, &record, args.time);
+        let converted = if args.pretty {
+            serde_json::from_str::<Value>(&converted)
+                .and_then(|value| serde_json::to_string_pretty(&value))
+                .unwrap_or(converted)
+        } else {
+            converted
+        };
+
+        writeln!(out, "{}", converted)
+            .map_err(|err| format!("Output could not be written: {}", err))?;
+    }
+    Ok(())
+}
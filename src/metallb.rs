@@ -1,11 +1,26 @@
 use chrono::{DateTime, FixedOffset};
 use serde_json::Value;
 
+use crate::config::Config;
+use crate::converter::{Context, LogConverter, Outcome};
 use crate::model::ErrorOrString;
 use crate::model::EventOrString;
 use crate::model::FluentBitJson;
 
-pub fn convert_metallb_logs(json: &mut FluentBitJson) {
+pub struct MetallbConverter;
+
+impl LogConverter for MetallbConverter {
+    fn name(&self) -> &str {
+        "metallb"
+    }
+
+    fn convert(&self, json: &mut FluentBitJson, context: &Context) -> Outcome {
+        convert_metallb_logs(json, context.config);
+        Outcome::Full
+    }
+}
+
+fn convert_metallb_logs(json: &mut FluentBitJson, config: &Config) {
     let event_or_op = extract_op(json);
     let level = json.other.remove("level").and_then(|level| match level {
         Value::String(level) => Some(level),
@@ -15,7 +30,7 @@ pub fn convert_metallb_logs(json: &mut FluentBitJson) {
     // @timestamp
     match convert_ts(json) {
         TsParseResult::Ok(ts) => json.timestamp = Some(ts),
-        TsParseResult::Err(ts) => json.misc.push(format!("ts:{}", ts)),
+        TsParseResult::Err(ts) => json.add_non_ecs("ts", ts),
         TsParseResult::None => {}
     }
 
@@ -25,7 +40,7 @@ pub fn convert_metallb_logs(json: &mut FluentBitJson) {
         (Some(Value::String(msg)), Some(ErrorOrString::String(error))) => {
             json.message = Some(error.to_string());
             json.error().message = Some(error.to_string());
-            json.misc.push(format!("msg:{}", msg));
+            json.add_non_ecs("msg", msg);
         }
         (Some(Value::String(msg)), _) => {
             json.message = Some(msg);
@@ -62,11 +77,10 @@ pub fn convert_metallb_logs(json: &mut FluentBitJson) {
                 event.category = vec!["network".to_string()];
             }
         };
-        match &level {
-            Some(level) => {
-                event.severity = convert_severity(&level);
-            }
-            _ => {}
+        if let Some(level) = &level {
+            event.severity = config
+                .severity("metallb", level)
+                .or_else(|| convert_severity(level));
         };
     }
 
@@ -75,17 +89,13 @@ pub fn convert_metallb_logs(json: &mut FluentBitJson) {
     let logger = json.other.remove("logger");
     {
         let log = json.log();
-        match level {
-            Some(level) => log.level = Some(level),
-            _ => {}
+        if let Some(level) = level {
+            log.level = Some(level)
         };
-        match caller {
-            Some((file, line)) => {
-                let orign_file = log.origin().file();
-                orign_file.name = Some(file);
-                orign_file.line = Some(line);
-            }
-            _ => {}
+        if let Some((file, line)) = caller {
+            let orign_file = log.origin().file();
+            orign_file.name = Some(file);
+            orign_file.line = Some(line);
         }
         if let Some(Value::String(logger)) = logger {
             log.logger = Some(logger);
@@ -97,22 +107,31 @@ pub fn convert_metallb_logs(json: &mut FluentBitJson) {
         json.network().protocol = Some(protocol);
     }
 
+    // related
+    if let Some(Value::Array(ips)) = json.other.remove("ips") {
+        for ip in ips {
+            match ip {
+                Value::String(ip) => json.related().add_ip(&ip),
+                ip => json.add_non_ecs("ips", ip),
+            }
+        }
+    }
+
     // Remove non-ecs mappings if they exist
-    json.move_key_to_misc("ips");
-    json.move_key_to_misc("interface");
-    json.move_key_to_misc("ips");
-    json.move_key_to_misc("pool");
-    json.move_key_to_misc("controller");
-    json.move_key_to_misc("name");
-    json.move_key_to_misc("gvk");
-    json.move_key_to_misc("IPAdvertisement");
-    json.move_key_to_misc("service");
-    json.move_key_to_misc("localIfs");
-    json.move_key_to_misc("reason");
-    json.move_key_to_misc("expected");
-    json.move_key_to_misc("joined");
-    json.move_key_to_misc("start reconcile");
-    json.move_key_to_misc("end reconcile");
+    json.move_key_to_non_ecs("ips");
+    json.move_key_to_non_ecs("interface");
+    json.move_key_to_non_ecs("pool");
+    json.move_key_to_non_ecs("controller");
+    json.move_key_to_non_ecs("name");
+    json.move_key_to_non_ecs("gvk");
+    json.move_key_to_non_ecs("IPAdvertisement");
+    json.move_key_to_non_ecs("service");
+    json.move_key_to_non_ecs("localIfs");
+    json.move_key_to_non_ecs("reason");
+    json.move_key_to_non_ecs("expected");
+    json.move_key_to_non_ecs("joined");
+    json.move_key_to_non_ecs("start reconcile");
+    json.move_key_to_non_ecs("end reconcile");
 }
 
 fn convert_action(ev: &str) -> Option<String> {
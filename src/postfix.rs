@@ -1,14 +1,25 @@
-use chrono::{DateTime, Datelike, FixedOffset, NaiveDate};
+use chrono::{DateTime, FixedOffset, NaiveTime};
+use chrono_tz::Tz;
 
 use log::warn;
 use pest::Parser;
 use pest_derive::Parser;
 
+use crate::config::Config;
+use crate::converter::{Context, LogConverter, Outcome};
 use crate::model::{ecs::NetworkEndpoint, FluentBitJson, LogOrString};
+use crate::timestamp;
 
 #[derive(Parser)]
 #[grammar_inline = r#"
-postfix_log = { SOI ~ timestamp ~ " " ~ host ~ " " ~ process_message ~ EOI}
+postfix_log = { SOI ~ (syslog_header | syslog_rfc5424_header) ~ process_message ~ EOI}
+
+// e.g. "Nov 23 19:07:33 host " or "2023-11-23T19:07:33.123456+01:00 host "
+syslog_header = _{ (timestamp | timestamp_rfc3339) ~ " " ~ host ~ " " }
+// https://datatracker.ietf.org/doc/html/rfc5424#section-6, e.g. "<22>1 2023-11-23T19:07:33.123Z host "
+// A missing timestamp is logged as NILVALUE "-"
+syslog_rfc5424_header = _{ "<" ~ ASCII_DIGIT{1,3} ~ ">1 " ~ (timestamp_rfc3339 | "-") ~ " " ~ host ~ " " }
+timestamp_rfc3339 = @{ ASCII_DIGIT{4} ~ "-" ~ ASCII_DIGIT{2} ~ "-" ~ ASCII_DIGIT{2} ~ "T" ~ not_space+ }
 
 timestamp = { month ~ " "+ ~ day ~ " "+ ~ hour ~ ":" ~ minute ~ ":" ~ second }
 month = { "Jan" | "Feb" | "Mar" | "Apr" | "May" | "Jun" | "Jul" | "Aug" | "Sep" | "Oct" | "Nov" | "Dec" }
@@ -19,39 +30,104 @@ second = { ASCII_DIGIT{1,2} }
 
 host = { not_space+ }
 pid = { ASCII_DIGIT+ }
+// "[PID]: " in BSD syslog, "PROCID MSGID STRUCTURED-DATA " in RFC 5424 where PROCID may be "-"
+process_id = _{ "[" ~ pid ~ "]: " | " " ~ (pid | "-") ~ " " ~ not_space+ ~ " " ~ structured_data ~ " " }
+structured_data = _{ "-" | ("[" ~ (!"]" ~ ANY)* ~ "]")+ }
 
-process_message = { process_smtpd | process_qmgr | process_smtp_lmtp | process_cleanup | process_postfix_script | process_anvil | process_master | process_main | process_other }
+process_message = { process_smtpd | process_postscreen | process_dnsblog | process_qmgr | process_delivery | process_bounce | process_pickup | process_cleanup | process_postfix_script | process_anvil | process_master | process_main | process_other }
 
-process_smtpd = { "postfix/smtpd" ~ "[" ~ pid ~ "]: " ~ log_level ~ message_smtpd }
-message_smtpd = { smtpd_connect | smtpd_disconnect | smtpd_lost_connection | smtpd_auth_failed | smtpd_mail_open_stream | message_other }
+process_smtpd = { "postfix/smtpd" ~ process_id ~ log_level ~ message_smtpd }
+message_smtpd = { smtpd_connect | smtpd_disconnect | smtpd_lost_connection | smtpd_auth_failed | smtpd_mail_open_stream | smtpd_reject | tls_established | tls_peer_certificate | message_other }
 smtpd_connect = { "connect from " ~ hostname_ip}
 smtpd_disconnect = { "disconnect from " ~ hostname_ip ~ ANY* }
 smtpd_lost_connection = {smtpd_lost_connection_msg ~ " from " ~ hostname_ip ~ ANY* }
 smtpd_lost_connection_msg = {"lost connection after " ~ not_space+ }
-smtpd_auth_failed = { hostname_ip ~ ": SASL " ~ not_space+ ~ " authentication failed: " ~ ANY*}
+// newer versions append the user, e.g. "authentication failed: (reason unavailable), sasl_username=joe"
+smtpd_auth_failed = { hostname_ip ~ ": SASL " ~ sasl_method ~ " authentication failed: " ~ sasl_reason ~ (", sasl_username=" ~ sasl_username)? }
+sasl_method = { not_space+ }
+sasl_reason = { (!", sasl_username=" ~ ANY)* }
+sasl_username = { ANY* }
+smtpd_reject = { queue_id ~ ": reject: " ~ smtp_command ~ " from " ~ (hostname_ip | ip_port) ~ ": " ~ smtp_reply_code ~ " " ~ smtp_status_code ~ " " ~ ("<" ~ reject_object ~ ">: ")? ~ reject_reason ~ "; " ~ reject_envelope }
+smtp_command = { (ASCII_ALPHA_UPPER | "-")+ }
+smtp_reply_code = { ASCII_DIGIT{3} }
+smtp_status_code = { ASCII_DIGIT ~ "." ~ ASCII_DIGIT{1,3} ~ "." ~ ASCII_DIGIT{1,3} }
+reject_object = { (!">" ~ ANY)* }
+reject_reason = { (!("; " ~ ("from=<" | "proto=")) ~ ANY)+ }
+// CONNECT and HELO rejects have no sender, e.g. "; proto=SMTP helo=<c>"
+reject_envelope = { ("from=<" ~ reject_from ~ ">")? ~ (","? ~ " to=<" ~ reject_to ~ ">")? ~ (","? ~ " "? ~ "proto=" ~ reject_proto)? ~ (","? ~ " helo=<" ~ reject_helo ~ ">")? ~ ANY* }
+reject_from = { (!">" ~ ANY)* }
+reject_to = { (!">" ~ ANY)* }
+reject_proto = { (!("," | " ") ~ ANY)+ }
+reject_helo = { (!">" ~ ANY)* }
 smtpd_mail_open_stream = { queue_id ~ ": client=" ~ hostname_ip ~ (", " ~ key_value_pair*)? }
 
-process_qmgr = { "postfix/qmgr" ~ "[" ~ pid ~ "]: " ~ log_level ~ message_qmgr }
+// http://www.postfix.org/POSTSCREEN_README.html
+process_postscreen = { "postfix/postscreen" ~ process_id ~ log_level ~ message_postscreen }
+message_postscreen = { postscreen_connect | postscreen_pass | postscreen_dnsbl_rank | postscreen_pregreet | postscreen_hangup | postscreen_disconnect | smtpd_reject | message_other }
+postscreen_connect = { "CONNECT from " ~ ip_port ~ " to " ~ postscreen_local }
+postscreen_local = { ip_port }
+postscreen_pass = { "PASS " ~ postscreen_pass_kind ~ " " ~ ip_port }
+postscreen_pass_kind = { "NEW" | "OLD" }
+postscreen_dnsbl_rank = { "DNSBL rank " ~ dnsbl_rank ~ " for " ~ ip_port }
+dnsbl_rank = { "-"? ~ ASCII_DIGIT+ }
+postscreen_pregreet = { "PREGREET " ~ ASCII_DIGIT+ ~ " after " ~ postscreen_delay ~ " from " ~ ip_port ~ ANY* }
+postscreen_hangup = { "HANGUP after " ~ postscreen_delay ~ " from " ~ ip_port ~ ANY* }
+postscreen_delay = { (ASCII_DIGIT | ".")+ }
+postscreen_disconnect = { "DISCONNECT " ~ ip_port }
+
+process_dnsblog = { "postfix/dnsblog" ~ process_id ~ log_level ~ message_dnsblog }
+message_dnsblog = { dnsblog_listed | message_other }
+dnsblog_listed = { "addr " ~ dnsbl_addr ~ " listed by domain " ~ dnsbl_domain ~ " as " ~ dnsbl_reply }
+dnsbl_addr = { not_space+ }
+dnsbl_domain = { not_space+ }
+dnsbl_reply = { not_space+ }
+
+process_qmgr = { "postfix/qmgr" ~ process_id ~ log_level ~ message_qmgr }
 message_qmgr = { qmgr_queue_active | qmgr_queue_removed | message_other}
 qmgr_queue_active = { queue_id ~ ": " ~ key_value_pair* ~ "(queue active)" }
 qmgr_queue_removed = { queue_id ~ ": removed" }
 
-process_smtp_lmtp = { "postfix/" ~ (process_smtp | process_lmtp) ~ "[" ~ pid ~ "]: " ~ log_level ~ message_smtp_lmtp }
-process_smtp = {"smtp"}
-process_lmtp = {"lmtp"}
-message_smtp_lmtp = { smtp_lmtp_transfer_mail | message_other}
-smtp_lmtp_transfer_mail = { queue_id ~ ": " ~ key_value_pair+ ~ "(" ~ ("connect to " ~ hostname_ip)? ~ (!(")") ~ ANY)* ~ ")"}
-
-process_cleanup = { "postfix/cleanup" ~ "[" ~ pid ~ "]: " ~ log_level ~ message_cleanup }
-message_cleanup = { cleanup_key_values | message_other }
+// delivery agents
+process_delivery = { "postfix/" ~ delivery_agent ~ process_id ~ log_level ~ message_delivery }
+delivery_agent = { "smtp" | "lmtp" | "local" | "virtual" | "pipe" | "error" }
+message_delivery = { delivery_status | delivery_connect_failed | tls_established | tls_peer_certificate | message_other}
+delivery_status = { queue_id ~ ": " ~ key_value_pair+ ~ "(" ~ delivery_response ~ ")"}
+delivery_connect_failed = { "connect to " ~ hostname_ip ~ ": " ~ connect_error }
+connect_error = { ANY* }
+// The response may contain parentheses itself, e.g. "(in reply to RCPT TO command)".
+delivery_response = { ("connect to " ~ hostname_ip)? ~ (!(")" ~ EOI) ~ ANY)* }
+
+process_bounce = { "postfix/bounce" ~ process_id ~ log_level ~ message_bounce }
+message_bounce = { bounce_notification | message_other }
+bounce_notification = { queue_id ~ ": sender " ~ bounce_kind ~ " notification: " ~ bounce_queue_id }
+bounce_kind = { "non-delivery" | "delivery status" | "delay" }
+bounce_queue_id = { queue_id }
+
+process_pickup = { "postfix/pickup" ~ process_id ~ log_level ~ message_pickup }
+message_pickup = { pickup_queued | message_other }
+pickup_queued = { queue_id ~ ": " ~ key_value_pair+ }
+
+process_cleanup = { "postfix/cleanup" ~ process_id ~ log_level ~ message_cleanup }
+message_cleanup = { cleanup_milter | cleanup_content_filter | cleanup_key_values | message_other }
+// e.g. "milter-reject: END-OF-MESSAGE from host[ip]: 5.7.1 Spam; from=<a> to=<b> proto=ESMTP helo=<c>"
+cleanup_milter = { queue_id ~ ": milter-" ~ filter_action ~ ": " ~ milter_stage ~ " from " ~ hostname_ip ~ ": " ~ (smtp_status_code ~ " ")? ~ filter_reason ~ "; " ~ reject_envelope }
+milter_stage = { (ASCII_ALPHA_UPPER | "-")+ }
+// e.g. "reject: header Subject: Spam from host[ip]; from=<a> to=<b> proto=ESMTP helo=<c>: 5.7.1 Spam"
+cleanup_content_filter = { queue_id ~ ": " ~ filter_action ~ ": " ~ filter_match ~ " from " ~ filter_client ~ "; " ~ filter_envelope ~ (": " ~ (smtp_status_code ~ " ")? ~ filter_reason)? }
+filter_action = { "reject" | "hold" | "discard" }
+filter_match = { ("header" | "body") ~ " " ~ (!(" from " ~ filter_client ~ "; ") ~ ANY)+ }
+// mail submitted with sendmail is logged as "from local"
+filter_client = _{ hostname_ip | "local" }
+filter_reason = { (!("; from=<") ~ ANY)* }
+filter_envelope = { "from=<" ~ reject_from ~ ">" ~ (" to=<" ~ reject_to ~ ">")? ~ (" proto=" ~ reject_proto)? ~ (" helo=<" ~ reject_helo ~ ">")? }
 cleanup_key_values = { queue_id ~ ": " ~ key_value_pair+}
 
-process_postfix_script = { "postfix/postfix-script" ~ "[" ~ pid ~ "]: "~ log_level ~ message_postfix_script }
+process_postfix_script = { "postfix/postfix-script" ~ process_id ~ log_level ~ message_postfix_script }
 message_postfix_script = { postfix_script_starting_postfix | postfix_script_group_writable | message_other }
 postfix_script_starting_postfix = { "starting the Postfix mail system" }
 postfix_script_group_writable = { "group or other writable:" ~ ANY* }
 
-process_anvil = { "postfix/anvil" ~ "[" ~ pid ~ "]: "~ message_anvil }
+process_anvil = { "postfix/anvil" ~ process_id ~ message_anvil }
 message_anvil = { anvil_rate | anvil_count | anvil_cache | message_other }
 anvil_rate = { "statistics: max " ~ anvil_metric_type ~ " rate " ~ ASCII_DIGIT+ ~ "/" ~ ASCII_DIGIT+ ~ "s for (" ~ anvil_protocol ~ ":" ~ ip ~") at "~ timestamp ~ ANY* }
 anvil_count = { "statistics: max " ~ anvil_metric_type ~ " count " ~ ASCII_DIGIT+ ~ " for (" ~ anvil_protocol ~ ":" ~ ip ~") at "~ timestamp ~ ANY*}
@@ -59,17 +135,27 @@ anvil_cache = { "statistics: max cache size " ~ ASCII_DIGIT+ ~ " at " ~ timestam
 anvil_metric_type = { "connection" | "message" | "recipient" | "newtls" | "auth" }
 anvil_protocol = { (!(":") ~ ANY)+}
 
-process_master = { "postfix/master" ~ "[" ~ pid ~ "]: " ~ log_level ~ message_master }
+process_master = { "postfix/master" ~ process_id ~ log_level ~ message_master }
 message_master = { master_daemon_started | message_other }
 master_daemon_started = { "daemon started -- " ~ ANY* }
 
-process_main = { "postfix" ~ "[" ~ pid ~ "]: "~ log_level ~ message_main }
+process_main = { "postfix" ~ process_id ~ log_level ~ message_main }
 message_main = { message_other }
 
-process_other = { "postfix/" ~ process_name ~ "[" ~ pid ~ "]: "~ log_level ~ message_other }
-process_name = { not_bracket+ }
+process_other = { "postfix/" ~ process_name ~ process_id ~ log_level ~ message_other }
+process_name = { (!" " ~ not_bracket)+ }
 message_other = { ANY* }
 
+// TLS
+tls_established = { tls_trust ~ " TLS connection established " ~ ("from " | "to ") ~ hostname_ip ~ ": " ~ tls_protocol ~ "v" ~ tls_version ~ " with cipher " ~ tls_cipher ~ ANY* }
+tls_trust = { "Anonymous" | "Untrusted" | "Trusted" | "Verified" }
+tls_protocol = { "TLS" | "SSL" }
+tls_version = { (ASCII_DIGIT | ".")+ }
+tls_cipher = { not_space+ }
+tls_peer_certificate = { hostname_ip ~ ": subject_CN=" ~ tls_subject_cn ~ ", issuer" ~ "_CN"? ~ "=" ~ tls_issuer_cn ~ ANY* }
+tls_subject_cn = { (!"," ~ ANY)+ }
+tls_issuer_cn = { (!"," ~ ANY)+ }
+
 // log level
 log_level = { log_level_warning? }
 log_level_warning = { "warning: " }
@@ -82,13 +168,35 @@ value = { (!("," | " ") ~ ANY)+ }
 not_space = _{!" " ~ ANY}
 not_bracket = _{!("[" | "]" | "(" | ")" ) ~ ANY}
 hostname_ip = { hostname ~ "[" ~ ip ~ "]" ~ (":" ~ port)?}
+ip_port = { "[" ~ ip ~ "]" ~ ":" ~ port }
 hostname = { not_bracket+ }
 ip = { not_bracket+ }
 port = { ASCII_DIGIT+ }
 "#]
 struct PostfixLogParser;
 
-pub fn convert_postfix_logs(json: &mut FluentBitJson, event_date: &DateTime<FixedOffset>) {
+pub struct PostfixConverter;
+
+impl LogConverter for PostfixConverter {
+    fn name(&self) -> &str {
+        "postfix"
+    }
+
+    fn convert(&self, json: &mut FluentBitJson, context: &Context) -> Outcome {
+        convert_postfix_logs(json, context.event_date, context.config)
+    }
+}
+
+/// Whether the log line is a syslog line written by postfix.
+pub fn is_postfix_log(log: &str) -> bool {
+    PostfixLogParser::parse(Rule::postfix_log, log).is_ok()
+}
+
+fn convert_postfix_logs(
+    json: &mut FluentBitJson,
+    event_date: &DateTime<FixedOffset>,
+    config: &Config,
+) -> Outcome {
     let log = match json.log.as_ref() {
         Some(LogOrString::String(_)) => match json.log.take() {
             Some(LogOrString::String(log)) => Some(log),
@@ -100,10 +208,25 @@ pub fn convert_postfix_logs(json: &mut FluentBitJson, event_date: &DateTime<Fixe
     match log {
         None => {
             convert_log_missing(json);
+            Outcome::Fallback
         }
         Some(log) => match PostfixLogParser::parse(Rule::postfix_log, &log) {
-            Err(err) => convert_parse_error(json, err, log),
-            Ok(ast) => convert_parsed_logs(json, ast, event_date, &log),
+            Err(err) => {
+                convert_parse_error(json, err, log);
+                Outcome::Fallback
+            }
+            Ok(ast) => {
+                // The generic rules only keep the message without extracting anything from it.
+                let generic = ast.clone().flatten().any(|pair| {
+                    matches!(pair.as_rule(), Rule::process_other | Rule::message_other)
+                });
+                convert_parsed_logs(json, ast, event_date, &log, config);
+                if generic {
+                    Outcome::Partial
+                } else {
+                    Outcome::Full
+                }
+            }
         },
     }
 }
@@ -121,7 +244,7 @@ fn convert_log_missing(json: &mut FluentBitJson) {
 fn convert_parse_error(json: &mut FluentBitJson, err: pest::error::Error<Rule>, log: String) {
     json.message = Some(log);
 
-    let err = format!("fluent-ecs postfix parser failed:{}", err.to_string());
+    let err = format!("fluent-ecs postfix parser failed:{}", err);
     warn!("parsing failed: {}", err);
     json.error().message = Some(err);
 
@@ -139,6 +262,7 @@ fn convert_parsed_logs(
     pairs: pest::iterators::Pairs<'_, Rule>,
     event_date: &DateTime<FixedOffset>,
     log: &String,
+    config: &Config,
 ) {
     // event basics
     {
@@ -151,49 +275,66 @@ fn convert_parsed_logs(
     json.service().type_val = Some("postfix".to_string());
 
     let mut host = None;
+    let timezone = timestamp::timezone(json, config);
 
     for pair in pairs {
-        match pair.as_rule() {
-            Rule::postfix_log => {
-                for pair in pair.into_inner() {
-                    match pair.as_rule() {
-                        Rule::timestamp => {
-                            json.timestamp = convert_date(pair.into_inner(), event_date)
-                        }
-                        Rule::host => host = Some(pair.as_str()),
-                        Rule::process_message => {
-                            for pair in pair.into_inner() {
-                                match pair.as_rule() {
-                                    Rule::process_smtpd => {
-                                        convert_smtpd(json, pair.into_inner(), host)
-                                    }
-                                    Rule::process_qmgr => {
-                                        convert_qmgr(json, pair.into_inner(), host)
-                                    }
-                                    Rule::process_smtp_lmtp => {
-                                        convert_smtp_lmtp(json, pair.into_inner(), host)
-                                    }
-                                    Rule::process_cleanup => {
-                                        convert_cleanup(json, pair.into_inner(), host)
-                                    }
-                                    Rule::process_postfix_script => {
-                                        convert_postfix_script(json, pair.into_inner())
-                                    }
-                                    Rule::process_anvil => {
-                                        convert_anvil(json, pair.into_inner(), event_date)
-                                    }
-                                    Rule::process_master => convert_master(json, pair.into_inner()),
-                                    Rule::process_main => convert_main(json, pair.into_inner()),
-                                    Rule::process_other => convert_other(json, pair.into_inner()),
-                                    _ => {}
+        if pair.as_rule() == Rule::postfix_log {
+            for pair in pair.into_inner() {
+                match pair.as_rule() {
+                    Rule::timestamp => {
+                        json.timestamp = convert_date(pair.into_inner(), event_date, timezone)
+                    }
+                    Rule::timestamp_rfc3339 => {
+                        json.timestamp = DateTime::parse_from_rfc3339(pair.as_str()).ok()
+                    }
+                    Rule::host => host = Some(pair.as_str()),
+                    Rule::process_message => {
+                        for pair in pair.into_inner() {
+                            match pair.as_rule() {
+                                Rule::process_smtpd => {
+                                    convert_smtpd(json, pair.into_inner(), host, config)
+                                }
+                                Rule::process_postscreen => {
+                                    convert_postscreen(json, pair.into_inner(), host, config)
+                                }
+                                Rule::process_dnsblog => {
+                                    convert_dnsblog(json, pair.into_inner(), config)
                                 }
+                                Rule::process_qmgr => {
+                                    convert_qmgr(json, pair.into_inner(), host, config)
+                                }
+                                Rule::process_delivery => {
+                                    convert_delivery(json, pair.into_inner(), host, config)
+                                }
+                                Rule::process_bounce => {
+                                    convert_bounce(json, pair.into_inner(), host, config)
+                                }
+                                Rule::process_pickup => {
+                                    convert_pickup(json, pair.into_inner(), host, config)
+                                }
+                                Rule::process_cleanup => {
+                                    convert_cleanup(json, pair.into_inner(), host, config)
+                                }
+                                Rule::process_postfix_script => {
+                                    convert_postfix_script(json, pair.into_inner(), config)
+                                }
+                                Rule::process_anvil => {
+                                    convert_anvil(json, pair.into_inner(), event_date, timezone)
+                                }
+                                Rule::process_master => {
+                                    convert_master(json, pair.into_inner(), config)
+                                }
+                                Rule::process_main => convert_main(json, pair.into_inner(), config),
+                                Rule::process_other => {
+                                    convert_other(json, pair.into_inner(), config)
+                                }
+                                _ => {}
                             }
                         }
-                        _ => {}
                     }
+                    _ => {}
                 }
             }
-            _ => {}
         }
     }
 }
@@ -201,6 +342,7 @@ fn convert_parsed_logs(
 fn convert_date(
     pairs: pest::iterators::Pairs<'_, Rule>,
     event_date: &DateTime<FixedOffset>,
+    timezone: Tz,
 ) -> Option<DateTime<FixedOffset>> {
     let mut month: u32 = 0;
     let mut day: u32 = 0;
@@ -236,31 +378,15 @@ fn convert_date(
         }
     }
 
-    let year: i32 = match (month, event_date.month()) {
-        // If the postfix month is december and the fluent-bit event month is january
-        // than it's probably new year right now and the event actually still happened last year.
-        (12, 1) => event_date.year() - 1,
-        // if not the year should be tha same as the year in that the event arrived at fluent bit.
-        _ => event_date.year(),
-    };
-
-    // log::debug!(
-    //     "postfix date parts: year: {}, month: {}, day: {}, hour: {}, minute: {}, second: {}",
-    //     year, month, day, hour, minute, second
-    // );
-
-    Some(
-        NaiveDate::from_ymd_opt(year, month, day)?
-            .and_hms_opt(hour, minute, second)?
-            .and_utc()
-            .fixed_offset(),
-    )
+    let time = NaiveTime::from_hms_opt(hour, minute, second)?;
+    timestamp::complete_date(month, day, time, event_date, timezone)
 }
 
 fn convert_smtpd(
     json: &mut FluentBitJson,
     pairs: pest::iterators::Pairs<'_, Rule>,
     host: Option<&str>,
+    config: &Config,
 ) {
     json.process().name = Some("smtpd".to_string());
 
@@ -269,7 +395,7 @@ fn convert_smtpd(
     for pair in pairs {
         match pair.as_rule() {
             Rule::pid => convert_pid(json, pair.as_str()),
-            Rule::log_level => convert_log_level(json, pair.into_inner()),
+            Rule::log_level => convert_log_level(json, pair.into_inner(), config),
             Rule::message_smtpd => {
                 json.message = Some(pair.as_str().to_string());
                 for pair in pair.into_inner() {
@@ -329,10 +455,33 @@ fn convert_smtpd(
                             let event = json.event();
                             event.category.push("authentication".to_string());
                             event.type_val.push("protocol".to_string());
+                            event.type_val.push("start".to_string());
+                            event.action = Some("sasl-authentication".to_string());
                             event.outcome = Some("failure".to_string());
                             event.severity = Some(300);
 
-                            convert_source(json, pair.into_inner());
+                            for pair in pair.into_inner() {
+                                match pair.as_rule() {
+                                    Rule::hostname_ip => {
+                                        convert_hostname_ip(json.source(), pair.into_inner())
+                                    }
+                                    Rule::sasl_method => json.add_structured_non_ecs(
+                                        "sasl",
+                                        serde_json::json!({ "method": pair.as_str() }),
+                                    ),
+                                    // e.g. "authentication failure" or the base64 encoded challenge
+                                    Rule::sasl_reason => {
+                                        let reason = pair.as_str().trim();
+                                        if !reason.is_empty() {
+                                            json.error().message = Some(reason.to_string())
+                                        }
+                                    }
+                                    Rule::sasl_username => {
+                                        json.user().name = Some(pair.as_str().to_string())
+                                    }
+                                    _ => {}
+                                }
+                            }
                         }
                         Rule::smtpd_mail_open_stream => {
                             let event = json.event();
@@ -343,6 +492,7 @@ fn convert_smtpd(
                             network.protocol = Some("smtp".to_string());
                             network.transport = Some("tcp".to_string());
 
+                            let mut sasl = serde_json::Map::new();
                             for pair in pair.into_inner() {
                                 match pair.as_rule() {
                                     Rule::queue_id => convert_queue_id(json, pair.as_str(), host),
@@ -350,19 +500,39 @@ fn convert_smtpd(
                                         convert_hostname_ip(json.source(), pair.into_inner())
                                     }
                                     Rule::key_value_pair => {
-                                        if let Some(("sasl_username", value)) =
-                                            convert_key_value(pair.into_inner())
-                                        {
-                                            json.event()
-                                                .category
-                                                .push("authentication".to_string());
-                                            json.event().type_val.push("user".to_string());
-                                            json.user().name = Some(value.to_string());
+                                        match convert_key_value(pair.into_inner()) {
+                                            Some(("sasl_username", value)) => {
+                                                let event = json.event();
+                                                event.category.push("authentication".to_string());
+                                                event.type_val.push("user".to_string());
+                                                event.type_val.push("start".to_string());
+                                                event.action =
+                                                    Some("sasl-authentication".to_string());
+                                                json.user().name = Some(value.to_string());
+                                            }
+                                            Some(("sasl_method", value)) => {
+                                                sasl.insert("method".to_string(), value.into());
+                                            }
+                                            Some(("sasl_sender", value)) => {
+                                                sasl.insert("sender".to_string(), value.into());
+                                            }
+                                            _ => {}
                                         }
                                     }
                                     _ => {}
                                 }
                             }
+                            if !sasl.is_empty() {
+                                json.add_structured_non_ecs("sasl", sasl);
+                            }
+                        }
+                        Rule::smtpd_reject => convert_smtpd_reject(json, pair.into_inner(), host),
+                        Rule::tls_established | Rule::tls_peer_certificate => {
+                            let network = json.network();
+                            network.protocol = Some("smtp".to_string());
+                            network.transport = Some("tcp".to_string());
+
+                            convert_tls(json, pair, TlsPeer::Client)
                         }
                         _ => {}
                     }
@@ -373,101 +543,411 @@ fn convert_smtpd(
     }
 }
 
-fn convert_smtp_lmtp(
+fn convert_delivery(
     json: &mut FluentBitJson,
     pairs: pest::iterators::Pairs<'_, Rule>,
     host: Option<&str>,
+    config: &Config,
 ) {
     json.event().category.push("email".to_string());
 
+    // smtp and lmtp transfer the mail to another server, the other agents deliver it locally.
+    let mut action = "mail-delivery";
+
     for pair in pairs {
         match pair.as_rule() {
             Rule::pid => convert_pid(json, pair.as_str()),
-            Rule::log_level => convert_log_level(json, pair.into_inner()),
-            Rule::process_lmtp => {
-                json.process().name = Some("lmtp".to_string());
-                json.network().protocol = Some("lmtp".to_string());
-                json.network().transport = Some("tcp".to_string());
-            }
-            Rule::process_smtp => {
-                json.process().name = Some("smtp".to_string());
-                json.network().protocol = Some("smtp".to_string());
-                json.network().transport = Some("tcp".to_string());
-            }
-            Rule::message_smtp_lmtp => {
+            Rule::log_level => convert_log_level(json, pair.into_inner(), config),
+            Rule::delivery_agent => {
+                json.process().name = Some(pair.as_str().to_string());
+                if matches!(pair.as_str(), "smtp" | "lmtp") {
+                    action = "mail-transfer";
+                    json.network().protocol = Some(pair.as_str().to_string());
+                    json.network().transport = Some("tcp".to_string());
+                }
+            }
+            Rule::message_delivery => {
                 json.message = Some(pair.as_str().to_string());
                 for pair in pair.into_inner() {
-                    match pair.as_rule() {
-                        Rule::smtp_lmtp_transfer_mail => {
-                            json.event().action = Some("mail-transfer".to_string());
-                            json.event().category.push("protocol".to_string());
-                            for pair in pair.into_inner() {
-                                match pair.as_rule() {
-                                    Rule::queue_id => convert_queue_id(json, pair.as_str(), host),
-                                    Rule::hostname_ip => {
-                                        convert_hostname_ip(json.destination(), pair.into_inner())
+                    if matches!(
+                        pair.as_rule(),
+                        Rule::tls_established | Rule::tls_peer_certificate
+                    ) {
+                        convert_tls(json, pair, TlsPeer::Server);
+                    } else if pair.as_rule() == Rule::delivery_connect_failed {
+                        let event = json.event();
+                        event.category.push("network".to_string());
+                        event.type_val.push("connection".to_string());
+                        event.type_val.push("start".to_string());
+                        event.action = Some("connect".to_string());
+                        event.outcome = Some("failure".to_string());
+                        event.severity = Some(300);
+
+                        for pair in pair.into_inner() {
+                            match pair.as_rule() {
+                                Rule::hostname_ip => {
+                                    convert_hostname_ip(json.destination(), pair.into_inner())
+                                }
+                                Rule::connect_error => {
+                                    json.error().message = Some(pair.as_str().to_string())
+                                }
+                                _ => {}
+                            }
+                        }
+                    } else if pair.as_rule() == Rule::delivery_status {
+                        json.event().action = Some(action.to_string());
+                        if action == "mail-transfer" {
+                            json.event().type_val.push("protocol".to_string());
+                        }
+                        for pair in pair.into_inner() {
+                            match pair.as_rule() {
+                                Rule::queue_id => convert_queue_id(json, pair.as_str(), host),
+                                Rule::key_value_pair => {
+                                    if let Some((key, value)) = convert_key_value(pair.into_inner())
+                                    {
+                                        convert_delivery_key_value(json, key, value)
                                     }
-                                    Rule::key_value_pair => {
-                                        match convert_key_value(pair.into_inner()) {
-                                            Some(("dsn", value)) => match value.get(..2) {
-                                                Some("2.") => {
-                                                    json.event().severity = Some(200);
-                                                    json.event().outcome =
-                                                        Some("success".to_string());
-                                                }
-                                                Some("4.") | Some("5.") => {
-                                                    json.event().severity = Some(300);
-                                                    json.event().outcome =
-                                                        Some("failure".to_string());
-                                                }
-                                                _ => {
-                                                    json.event().severity = Some(200);
-                                                    json.event().outcome =
-                                                        Some("unknown".to_string());
-                                                }
-                                            },
-                                            Some(("relay", value)) => {
-                                                match PostfixLogParser::parse(
-                                                    Rule::hostname_ip,
-                                                    &value,
-                                                ) {
-                                                    Err(_err) => {}
-                                                    Ok(ast) => {
-                                                        for pair in ast {
-                                                            match pair.as_rule() {
-                                                                Rule::hostname_ip => {
-                                                                    convert_hostname_ip(
-                                                                        json.destination(),
-                                                                        pair.into_inner(),
-                                                                    )
-                                                                }
-                                                                _ => {}
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                            _ => {}
+                                }
+                                Rule::delivery_response => {
+                                    for pair in pair.clone().into_inner() {
+                                        if pair.as_rule() == Rule::hostname_ip {
+                                            convert_hostname_ip(
+                                                json.destination(),
+                                                pair.into_inner(),
+                                            )
                                         }
                                     }
-                                    _ => {}
+                                    json.add_structured_non_ecs("response", pair.as_str());
                                 }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn convert_delivery_key_value(json: &mut FluentBitJson, key: &str, value: &str) {
+    match key {
+        //https://www.iana.org/assignments/smtp-enhanced-status-codes/smtp-enhanced-status-codes.xhtml
+        "dsn" => match value.get(..2) {
+            Some("2.") => {
+                json.event().severity = Some(200);
+                json.event().outcome = Some("success".to_string());
+            }
+            Some("4.") | Some("5.") => {
+                json.event().severity = Some(300);
+                json.event().outcome = Some("failure".to_string());
+            }
+            _ => {
+                json.event().severity = Some(200);
+                json.event().outcome = Some("unknown".to_string());
+            }
+        },
+        "status" => {
+            let outcome = match value {
+                "sent" | "deliverable" => "success",
+                "deferred" | "bounced" | "expired" | "undeliverable" => "failure",
+                _ => "unknown",
+            };
+            json.event().outcome = Some(outcome.to_string());
+            json.event().reason = Some(value.to_string());
+        }
+        "relay" => {
+            if let Ok(ast) = PostfixLogParser::parse(Rule::hostname_ip, value) {
+                for pair in ast {
+                    if pair.as_rule() == Rule::hostname_ip {
+                        convert_hostname_ip(json.destination(), pair.into_inner())
+                    }
+                }
+            }
+        }
+        "to" => {
+            let to = value.trim_start_matches('<').trim_end_matches('>');
+            if !to.is_empty() {
+                json.email().to().address.push(to.to_string())
+            }
+        }
+        // The total time from the arrival of the mail until the delivery in seconds.
+        "delay" => {
+            if let Ok(delay) = value.parse::<f64>() {
+                json.event().duration = Some((delay * 1_000_000_000.0).round() as u64);
+            }
+        }
+        // delays=a/b/c/d: time before the queue manager, in the queue manager, for the connection
+        // setup and for the transmission.
+        "delays" => {
+            let phases: Vec<f64> = value.split('/').filter_map(|d| d.parse().ok()).collect();
+            if let [before_queue_manager, in_queue_manager, connection_setup, transmission] =
+                phases[..]
+            {
+                json.add_structured_non_ecs(
+                    "delays",
+                    serde_json::json!({
+                        "before_queue_manager": before_queue_manager,
+                        "in_queue_manager": in_queue_manager,
+                        "connection_setup": connection_setup,
+                        "transmission": transmission,
+                    }),
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+fn convert_smtpd_reject(
+    json: &mut FluentBitJson,
+    pairs: pest::iterators::Pairs<'_, Rule>,
+    host: Option<&str>,
+) {
+    let event = json.event();
+    event.category.push("network".to_string());
+    event.type_val.push("denied".to_string());
+    event.action = Some("reject".to_string());
+    event.outcome = Some("failure".to_string());
+    event.severity = Some(300);
+
+    let network = json.network();
+    network.protocol = Some("smtp".to_string());
+    network.transport = Some("tcp".to_string());
+
+    for pair in pairs.flatten() {
+        match pair.as_rule() {
+            Rule::queue_id if pair.as_str() != "NOQUEUE" => {
+                convert_queue_id(json, pair.as_str(), host)
+            }
+            Rule::smtp_command => json.add_non_ecs("command", pair.as_str()),
+            Rule::hostname_ip | Rule::ip_port => {
+                convert_hostname_ip(json.source(), pair.into_inner())
+            }
+            Rule::smtp_reply_code => {
+                if let Ok(reply_code) = pair.as_str().parse::<u16>() {
+                    json.add_non_ecs("reply_code", reply_code);
+                }
+            }
+            Rule::smtp_status_code => json.error().code = Some(pair.as_str().to_string()),
+            Rule::reject_reason => {
+                // e.g. "Service unavailable; client [192.0.2.1] blocked using zen.spamhaus.org"
+                if let Some((_, blocklist)) = pair.as_str().split_once("blocked using ") {
+                    let domain = blocklist.split([';', ' ', ',']).next().unwrap_or_default();
+                    json.add_non_ecs("dnsbl_domain", domain);
+                }
+                json.error().message = Some(pair.as_str().to_string())
+            }
+            Rule::reject_from if !pair.as_str().is_empty() => {
+                json.email().from().address.push(pair.as_str().to_string())
+            }
+            Rule::reject_to if !pair.as_str().is_empty() => {
+                json.email().to().address.push(pair.as_str().to_string())
+            }
+            Rule::reject_proto => json.add_non_ecs("proto", pair.as_str()),
+            Rule::reject_helo => json.add_non_ecs("helo", pair.as_str()),
+            _ => {}
+        }
+    }
+}
+
+/// The role of the peer of a TLS connection. smtpd accepts connections from clients while smtp
+/// and lmtp connect to servers.
+enum TlsPeer {
+    Client,
+    Server,
+}
+
+fn convert_tls(json: &mut FluentBitJson, pair: pest::iterators::Pair<'_, Rule>, peer: TlsPeer) {
+    let established = pair.as_rule() == Rule::tls_established;
+    if established {
+        let event = json.event();
+        event.category.push("network".to_string());
+        event.type_val.push("connection".to_string());
+        event.type_val.push("protocol".to_string());
+        event.outcome = Some("success".to_string());
+        json.tls().established = Some(true);
+    }
+
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::hostname_ip => {
+                let endpoint = match peer {
+                    TlsPeer::Client => json.source(),
+                    TlsPeer::Server => json.destination(),
+                };
+                convert_hostname_ip(endpoint, pair.into_inner())
+            }
+            Rule::tls_protocol => {
+                json.tls().version_protocol = Some(pair.as_str().to_lowercase());
+            }
+            Rule::tls_version => json.tls().version = Some(pair.as_str().to_string()),
+            Rule::tls_cipher => json.tls().cipher = Some(pair.as_str().to_string()),
+            Rule::tls_subject_cn => {
+                let subject = Some(format!("CN={}", pair.as_str()));
+                match peer {
+                    TlsPeer::Client => json.tls().client().subject = subject,
+                    TlsPeer::Server => json.tls().server().subject = subject,
+                }
+            }
+            Rule::tls_issuer_cn => {
+                let issuer = Some(format!("CN={}", pair.as_str()));
+                match peer {
+                    TlsPeer::Client => json.tls().client().issuer = issuer,
+                    TlsPeer::Server => json.tls().server().issuer = issuer,
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn convert_postscreen(
+    json: &mut FluentBitJson,
+    pairs: pest::iterators::Pairs<'_, Rule>,
+    host: Option<&str>,
+    config: &Config,
+) {
+    json.process().name = Some("postscreen".to_string());
+
+    json.event().category.push("email".to_string());
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::pid => convert_pid(json, pair.as_str()),
+            Rule::log_level => convert_log_level(json, pair.into_inner(), config),
+            Rule::message_postscreen => {
+                json.message = Some(pair.as_str().to_string());
+                for pair in pair.into_inner() {
+                    match pair.as_rule() {
+                        Rule::smtpd_reject => convert_smtpd_reject(json, pair.into_inner(), host),
+                        Rule::message_other => {}
+                        _ => convert_postscreen_event(json, pair),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Converts the postscreen verdicts about a client.
+fn convert_postscreen_event(json: &mut FluentBitJson, pair: pest::iterators::Pair<'_, Rule>) {
+    let network = json.network();
+    network.protocol = Some("smtp".to_string());
+    network.transport = Some("tcp".to_string());
+
+    let event = json.event();
+    event.category.push("network".to_string());
+    match pair.as_rule() {
+        Rule::postscreen_connect => {
+            event.type_val.push("connection".to_string());
+            event.type_val.push("start".to_string());
+            event.action = Some("connect".to_string());
+            event.outcome = Some("success".to_string());
+        }
+        Rule::postscreen_pass => {
+            event.type_val.push("allowed".to_string());
+            event.outcome = Some("success".to_string());
+        }
+        Rule::postscreen_dnsbl_rank => {
+            event.type_val.push("info".to_string());
+            event.action = Some("dnsbl".to_string());
+            event.outcome = Some("failure".to_string());
+            event.severity = Some(300);
+        }
+        Rule::postscreen_pregreet => {
+            event.type_val.push("protocol".to_string());
+            event.action = Some("pregreet".to_string());
+            event.outcome = Some("failure".to_string());
+            event.severity = Some(300);
+        }
+        Rule::postscreen_hangup => {
+            event.type_val.push("connection".to_string());
+            event.type_val.push("end".to_string());
+            event.action = Some("hangup".to_string());
+            event.outcome = Some("failure".to_string());
+            event.severity = Some(300);
+        }
+        Rule::postscreen_disconnect => {
+            event.type_val.push("connection".to_string());
+            event.type_val.push("end".to_string());
+            event.action = Some("disconnect".to_string());
+            event.outcome = Some("success".to_string());
+        }
+        _ => {}
+    }
+
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::ip_port => convert_hostname_ip(json.source(), pair.into_inner()),
+            Rule::postscreen_local => {
+                for pair in pair.into_inner() {
+                    convert_hostname_ip(json.destination(), pair.into_inner())
+                }
+            }
+            Rule::postscreen_pass_kind => {
+                let action = format!("pass-{}", pair.as_str().to_lowercase());
+                json.event().action = Some(action)
+            }
+            Rule::dnsbl_rank => {
+                if let Ok(rank) = pair.as_str().parse::<i32>() {
+                    json.add_non_ecs("dnsbl_rank", rank);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn convert_dnsblog(
+    json: &mut FluentBitJson,
+    pairs: pest::iterators::Pairs<'_, Rule>,
+    config: &Config,
+) {
+    json.process().name = Some("dnsblog".to_string());
+
+    json.event().category.push("email".to_string());
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::pid => convert_pid(json, pair.as_str()),
+            Rule::log_level => convert_log_level(json, pair.into_inner(), config),
+            Rule::message_dnsblog => {
+                json.message = Some(pair.as_str().to_string());
+                for pair in pair.into_inner() {
+                    if pair.as_rule() == Rule::dnsblog_listed {
+                        let event = json.event();
+                        event.category.push("network".to_string());
+                        event.type_val.push("info".to_string());
+                        event.action = Some("dnsbl".to_string());
+                        event.outcome = Some("failure".to_string());
+
+                        for pair in pair.into_inner() {
+                            match pair.as_rule() {
+                                Rule::dnsbl_addr => {
+                                    json.source().ip = Some(pair.as_str().to_string())
+                                }
+                                Rule::dnsbl_domain => {
+                                    json.add_non_ecs("dnsbl_domain", pair.as_str())
+                                }
+                                Rule::dnsbl_reply => json.add_non_ecs("dnsbl_reply", pair.as_str()),
+                                _ => {}
                             }
                         }
-                        _ => {}
                     }
                 }
             }
             _ => {}
         }
     }
-    //https://www.iana.org/assignments/smtp-enhanced-status-codes/smtp-enhanced-status-codes.xhtml
 }
 
 fn convert_qmgr(
     json: &mut FluentBitJson,
     pairs: pest::iterators::Pairs<'_, Rule>,
     host: Option<&str>,
+    config: &Config,
 ) {
     json.process().name = Some("qmgr".to_string());
     json.event().category.push("email".to_string());
@@ -475,18 +955,17 @@ fn convert_qmgr(
     for pair in pairs {
         match pair.as_rule() {
             Rule::pid => convert_pid(json, pair.as_str()),
-            Rule::log_level => convert_log_level(json, pair.into_inner()),
+            Rule::log_level => convert_log_level(json, pair.into_inner(), config),
             Rule::message_qmgr => {
                 json.message = Some(pair.as_str().to_string());
                 for pair in pair.into_inner() {
                     match pair.as_rule() {
                         Rule::qmgr_queue_active | Rule::qmgr_queue_removed => {
                             for pair in pair.into_inner() {
-                                match pair.as_rule() {
-                                    Rule::queue_id => convert_queue_id(json, pair.as_str(), host),
-                                    // The pairs contains "from" which is the SMTP "MAIL FROM". There is field for this
-                                    // in ECS. The E-Mail fields in ECS are intended for actual mail content.
-                                    _ => {}
+                                // The pairs contains "from" which is the SMTP "MAIL FROM". There is field for this
+                                // in ECS. The E-Mail fields in ECS are intended for actual mail content.
+                                if pair.as_rule() == Rule::queue_id {
+                                    convert_queue_id(json, pair.as_str(), host)
                                 }
                             }
                         }
@@ -499,10 +978,109 @@ fn convert_qmgr(
     }
 }
 
+fn convert_bounce(
+    json: &mut FluentBitJson,
+    pairs: pest::iterators::Pairs<'_, Rule>,
+    host: Option<&str>,
+    config: &Config,
+) {
+    json.process().name = Some("bounce".to_string());
+    json.event().category.push("email".to_string());
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::pid => convert_pid(json, pair.as_str()),
+            Rule::log_level => convert_log_level(json, pair.into_inner(), config),
+            Rule::message_bounce => {
+                json.message = Some(pair.as_str().to_string());
+                for pair in pair.into_inner() {
+                    if pair.as_rule() == Rule::bounce_notification {
+                        json.event().type_val.push("info".to_string());
+                        for pair in pair.into_inner() {
+                            match pair.as_rule() {
+                                // The notification is a new mail. The transaction stays the one
+                                // of the original mail, so that both can be found together.
+                                Rule::queue_id => convert_queue_id(json, pair.as_str(), host),
+                                Rule::bounce_kind => {
+                                    let action = match pair.as_str() {
+                                        "non-delivery" => "bounce",
+                                        "delay" => "delay-notification",
+                                        _ => "delivery-notification",
+                                    };
+                                    json.event().action = Some(action.to_string());
+                                }
+                                Rule::bounce_queue_id => {
+                                    let queue_id = match host {
+                                        Some(host) => format!("{}.{}", host, pair.as_str()),
+                                        None => pair.as_str().to_string(),
+                                    };
+                                    json.add_non_ecs("notification_queue_id", queue_id);
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn convert_pickup(
+    json: &mut FluentBitJson,
+    pairs: pest::iterators::Pairs<'_, Rule>,
+    host: Option<&str>,
+    config: &Config,
+) {
+    json.process().name = Some("pickup".to_string());
+    json.event().category.push("email".to_string());
+
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::pid => convert_pid(json, pair.as_str()),
+            Rule::log_level => convert_log_level(json, pair.into_inner(), config),
+            Rule::message_pickup => {
+                json.message = Some(pair.as_str().to_string());
+                for pair in pair.into_inner() {
+                    if pair.as_rule() == Rule::pickup_queued {
+                        // Mails submitted locally with sendmail are picked up from the maildrop
+                        // queue.
+                        json.event().action = Some("mail-submission".to_string());
+                        for pair in pair.into_inner() {
+                            match pair.as_rule() {
+                                Rule::queue_id => convert_queue_id(json, pair.as_str(), host),
+                                Rule::key_value_pair => {
+                                    match convert_key_value(pair.into_inner()) {
+                                        Some(("uid", value)) => {
+                                            json.user().id = Some(value.to_string())
+                                        }
+                                        Some(("from", value)) => {
+                                            let from =
+                                                value.trim_start_matches('<').trim_end_matches('>');
+                                            if !from.is_empty() {
+                                                json.email().from().address.push(from.to_string())
+                                            }
+                                        }
+                                        _ => {}
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 fn convert_cleanup(
     json: &mut FluentBitJson,
     pairs: pest::iterators::Pairs<'_, Rule>,
     host: Option<&str>,
+    config: &Config,
 ) {
     json.process().name = Some("cleanup".to_string());
     json.event().category.push("email".to_string());
@@ -510,28 +1088,30 @@ fn convert_cleanup(
     for pair in pairs {
         match pair.as_rule() {
             Rule::pid => convert_pid(json, pair.as_str()),
-            Rule::log_level => convert_log_level(json, pair.into_inner()),
+            Rule::log_level => convert_log_level(json, pair.into_inner(), config),
             Rule::message_cleanup => {
                 json.message = Some(pair.as_str().to_string());
                 for pair in pair.into_inner() {
-                    match pair.as_rule() {
-                        Rule::cleanup_key_values => {
-                            for pair in pair.into_inner() {
-                                match pair.as_rule() {
-                                    Rule::queue_id => convert_queue_id(json, pair.as_str(), host),
-
-                                    Rule::key_value_pair => {
-                                        if let Some(("message-id", value)) =
-                                            convert_key_value(pair.into_inner())
-                                        {
-                                            json.email().message_id = Some(value.to_string());
-                                        }
+                    if matches!(
+                        pair.as_rule(),
+                        Rule::cleanup_milter | Rule::cleanup_content_filter
+                    ) {
+                        convert_cleanup_filter(json, pair, host);
+                    } else if pair.as_rule() == Rule::cleanup_key_values {
+                        for pair in pair.into_inner() {
+                            match pair.as_rule() {
+                                Rule::queue_id => convert_queue_id(json, pair.as_str(), host),
+
+                                Rule::key_value_pair => {
+                                    if let Some(("message-id", value)) =
+                                        convert_key_value(pair.into_inner())
+                                    {
+                                        json.email().message_id = Some(value.to_string());
                                     }
-                                    _ => {}
                                 }
+                                _ => {}
                             }
                         }
-                        _ => {}
                     }
                 }
             }
@@ -540,7 +1120,61 @@ fn convert_cleanup(
     }
 }
 
-fn convert_postfix_script(json: &mut FluentBitJson, pairs: pest::iterators::Pairs<'_, Rule>) {
+/// Converts the actions of milters and of header and body checks.
+fn convert_cleanup_filter(
+    json: &mut FluentBitJson,
+    pair: pest::iterators::Pair<'_, Rule>,
+    host: Option<&str>,
+) {
+    let milter = pair.as_rule() == Rule::cleanup_milter;
+
+    for pair in pair.into_inner().flatten() {
+        match pair.as_rule() {
+            Rule::queue_id => convert_queue_id(json, pair.as_str(), host),
+            Rule::filter_action => {
+                let event = json.event();
+                match pair.as_str() {
+                    // The mail is kept in the hold queue until an administrator releases it.
+                    "hold" => {
+                        event.type_val.push("info".to_string());
+                        event.outcome = Some("unknown".to_string());
+                    }
+                    _ => {
+                        event.type_val.push("denied".to_string());
+                        event.outcome = Some("failure".to_string());
+                        event.severity = Some(300);
+                    }
+                }
+                event.action = match milter {
+                    true => Some(format!("milter-{}", pair.as_str())),
+                    false => Some(pair.as_str().to_string()),
+                };
+            }
+            Rule::milter_stage => json.add_non_ecs("milter_stage", pair.as_str()),
+            Rule::filter_match => json.add_non_ecs("filter_match", pair.as_str()),
+            Rule::hostname_ip => convert_hostname_ip(json.source(), pair.into_inner()),
+            Rule::smtp_status_code => json.error().code = Some(pair.as_str().to_string()),
+            Rule::filter_reason if !pair.as_str().is_empty() => {
+                json.error().message = Some(pair.as_str().to_string())
+            }
+            Rule::reject_from if !pair.as_str().is_empty() => {
+                json.email().from().address.push(pair.as_str().to_string())
+            }
+            Rule::reject_to if !pair.as_str().is_empty() => {
+                json.email().to().address.push(pair.as_str().to_string())
+            }
+            Rule::reject_proto => json.add_non_ecs("proto", pair.as_str()),
+            Rule::reject_helo => json.add_non_ecs("helo", pair.as_str()),
+            _ => {}
+        }
+    }
+}
+
+fn convert_postfix_script(
+    json: &mut FluentBitJson,
+    pairs: pest::iterators::Pairs<'_, Rule>,
+    config: &Config,
+) {
     json.process().name = Some("postfix-script".to_string());
 
     json.event().category.push("email".to_string());
@@ -548,7 +1182,7 @@ fn convert_postfix_script(json: &mut FluentBitJson, pairs: pest::iterators::Pair
     for pair in pairs {
         match pair.as_rule() {
             Rule::pid => convert_pid(json, pair.as_str()),
-            Rule::log_level => convert_log_level(json, pair.into_inner()),
+            Rule::log_level => convert_log_level(json, pair.into_inner(), config),
             Rule::message_postfix_script => {
                 json.message = Some(pair.as_str().to_string());
                 for pair in pair.into_inner() {
@@ -577,6 +1211,7 @@ fn convert_anvil(
     json: &mut FluentBitJson,
     pairs: pest::iterators::Pairs<'_, Rule>,
     event_date: &DateTime<FixedOffset>,
+    timezone: Tz,
 ) {
     json.process().name = Some("anvil".to_string());
 
@@ -607,7 +1242,7 @@ fn convert_anvil(
                                     Rule::ip => json.source().ip = Some(pair.as_str().to_string()),
                                     Rule::timestamp => {
                                         json.event().end =
-                                            convert_date(pair.into_inner(), event_date)
+                                            convert_date(pair.into_inner(), event_date, timezone)
                                     }
                                     _ => {}
                                 }
@@ -622,7 +1257,11 @@ fn convert_anvil(
     }
 }
 
-fn convert_master(json: &mut FluentBitJson, pairs: pest::iterators::Pairs<'_, Rule>) {
+fn convert_master(
+    json: &mut FluentBitJson,
+    pairs: pest::iterators::Pairs<'_, Rule>,
+    config: &Config,
+) {
     json.process().name = Some("master".to_string());
 
     json.event().category.push("email".to_string());
@@ -630,18 +1269,15 @@ fn convert_master(json: &mut FluentBitJson, pairs: pest::iterators::Pairs<'_, Ru
     for pair in pairs {
         match pair.as_rule() {
             Rule::pid => convert_pid(json, pair.as_str()),
-            Rule::log_level => convert_log_level(json, pair.into_inner()),
+            Rule::log_level => convert_log_level(json, pair.into_inner(), config),
             Rule::message_master => {
                 json.message = Some(pair.as_str().to_string());
                 for pair in pair.into_inner() {
-                    match pair.as_rule() {
-                        Rule::master_daemon_started => {
-                            let event = json.event();
-                            event.category.push("process".to_string());
-                            event.type_val.push("start".to_string());
-                            event.outcome = Some("success".to_string());
-                        }
-                        _ => {}
+                    if pair.as_rule() == Rule::master_daemon_started {
+                        let event = json.event();
+                        event.category.push("process".to_string());
+                        event.type_val.push("start".to_string());
+                        event.outcome = Some("success".to_string());
                     }
                 }
             }
@@ -650,24 +1286,32 @@ fn convert_master(json: &mut FluentBitJson, pairs: pest::iterators::Pairs<'_, Ru
     }
 }
 
-fn convert_main(json: &mut FluentBitJson, pairs: pest::iterators::Pairs<'_, Rule>) {
+fn convert_main(
+    json: &mut FluentBitJson,
+    pairs: pest::iterators::Pairs<'_, Rule>,
+    config: &Config,
+) {
     json.event().category.push("email".to_string());
 
     for pair in pairs {
         match pair.as_rule() {
             Rule::pid => convert_pid(json, pair.as_str()),
-            Rule::log_level => convert_log_level(json, pair.into_inner()),
+            Rule::log_level => convert_log_level(json, pair.into_inner(), config),
             Rule::message_main => json.message = Some(pair.as_str().to_string()),
             _ => {}
         }
     }
 }
 
-fn convert_other(json: &mut FluentBitJson, pairs: pest::iterators::Pairs<'_, Rule>) {
+fn convert_other(
+    json: &mut FluentBitJson,
+    pairs: pest::iterators::Pairs<'_, Rule>,
+    config: &Config,
+) {
     for pair in pairs {
         match pair.as_rule() {
             Rule::pid => convert_pid(json, pair.as_str()),
-            Rule::log_level => convert_log_level(json, pair.into_inner()),
+            Rule::log_level => convert_log_level(json, pair.into_inner(), config),
             Rule::process_other => json.process().name = Some(pair.as_str().to_string()),
             Rule::message_other => json.message = Some(pair.as_str().to_string()),
             _ => {}
@@ -676,30 +1320,29 @@ fn convert_other(json: &mut FluentBitJson, pairs: pest::iterators::Pairs<'_, Rul
 }
 
 fn convert_pid(json: &mut FluentBitJson, pid: &str) {
-    match pid.parse::<u32>() {
-        Ok(pid) => json.process().pid = Some(pid),
-        Err(_) => {}
+    if let Ok(pid) = pid.parse::<u32>() {
+        json.process().pid = Some(pid)
     }
 }
 
-fn convert_log_level(json: &mut FluentBitJson, pairs: pest::iterators::Pairs<'_, Rule>) {
-    json.event().severity = Some(200);
+fn convert_log_level(
+    json: &mut FluentBitJson,
+    pairs: pest::iterators::Pairs<'_, Rule>,
+    config: &Config,
+) {
+    json.event().severity = config.severity("postfix", "info").or(Some(200));
     for pair in pairs {
-        match pair.as_rule() {
-            Rule::log_level_warning => {
-                json.event().severity = Some(300);
-                json.log().level = Some("warning".to_string());
-            }
-            _ => {}
+        if pair.as_rule() == Rule::log_level_warning {
+            json.event().severity = config.severity("postfix", "warning").or(Some(300));
+            json.log().level = Some("warning".to_string());
         }
     }
 }
 
 fn convert_source(json: &mut FluentBitJson, pairs: pest::iterators::Pairs<'_, Rule>) {
     for pair in pairs {
-        match pair.as_rule() {
-            Rule::hostname_ip => convert_hostname_ip(json.source(), pair.into_inner()),
-            _ => {}
+        if pair.as_rule() == Rule::hostname_ip {
+            convert_hostname_ip(json.source(), pair.into_inner())
         }
     }
 }
@@ -712,10 +1355,11 @@ fn convert_hostname_ip(json: &mut NetworkEndpoint, pairs: pest::iterators::Pairs
                 hostname => json.domain = Some(hostname.to_string()),
             },
             Rule::ip => json.ip = Some(pair.as_str().to_string()),
-            Rule::port => match pair.as_str().parse::<u16>() {
-                Ok(port) => json.port = Some(port),
-                Err(_) => {}
-            },
+            Rule::port => {
+                if let Ok(port) = pair.as_str().parse::<u16>() {
+                    json.port = Some(port)
+                }
+            }
             _ => {}
         }
     }
@@ -745,4 +1389,90 @@ fn convert_key_value(pairs: pest::iterators::Pairs<'_, Rule>) -> Option<(&str, &
         (Some(key), Some(value)) => Some((key, value)),
         _ => None,
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, FixedOffset};
+    use chrono_tz::Tz;
+    use pest::Parser;
+    use rstest::*;
+
+    use super::{convert_date, PostfixLogParser, Rule};
+
+    #[rstest]
+    #[case::same_year(
+        "Nov 16 06:23:07",
+        "2023-11-16T13:27:38+01:00",
+        "2023-11-16T06:23:07+00:00",
+        Tz::UTC
+    )]
+    #[case::previous_year(
+        "Dec 31 23:59:58",
+        "2024-01-01T00:00:03+00:00",
+        "2023-12-31T23:59:58+00:00",
+        Tz::UTC
+    )]
+    #[case::next_year(
+        "Jan  1 00:00:01",
+        "2023-12-31T23:59:59+00:00",
+        "2024-01-01T00:00:01+00:00",
+        Tz::UTC
+    )]
+    #[case::leap_day(
+        "Feb 29 12:00:00",
+        "2025-01-10T12:00:00+00:00",
+        "2024-02-29T12:00:00+00:00",
+        Tz::UTC
+    )]
+    #[case::daylight_saving_gap(
+        "Mar 26 02:30:00",
+        "2023-03-26T03:00:00+02:00",
+        "2023-03-26T03:30:00+02:00",
+        Tz::Europe__Berlin
+    )]
+    fn convert_date_year_test(
+        #[case] timestamp: &str,
+        #[case] event_date: &str,
+        #[case] expected: &str,
+        #[case] timezone: Tz,
+    ) -> Result<(), String> {
+        let date = convert_timestamp(timestamp, event_date, timezone)?;
+
+        let expected = DateTime::parse_from_rfc3339(expected).map_err(|err| err.to_string())?;
+        assert_eq!(date, Some(expected));
+
+        Ok(())
+    }
+
+    #[test]
+    fn convert_date_timezone_test() -> Result<(), String> {
+        let date = convert_timestamp(
+            "Jul  1 12:00:00",
+            "2023-07-01T10:00:05+00:00",
+            Tz::Europe__Berlin,
+        )?;
+
+        let expected = DateTime::parse_from_rfc3339("2023-07-01T12:00:00+02:00")
+            .map_err(|err| err.to_string())?;
+        assert_eq!(date, Some(expected));
+        assert_eq!(date.map(|date| *date.offset()), Some(*expected.offset()));
+
+        Ok(())
+    }
+
+    fn convert_timestamp(
+        timestamp: &str,
+        event_date: &str,
+        timezone: Tz,
+    ) -> Result<Option<DateTime<FixedOffset>>, String> {
+        let pair = PostfixLogParser::parse(Rule::timestamp, timestamp)
+            .map_err(|err| format!("Timestamp could not be parsed: {}", err))?
+            .next()
+            .ok_or("Timestamp rule did not match")?;
+        let event_date = DateTime::parse_from_rfc3339(event_date)
+            .map_err(|err| format!("Event date is invalid: {}", err))?;
+
+        Ok(convert_date(pair.into_inner(), &event_date, timezone))
+    }
+}
@@ -0,0 +1,26 @@
+use crate::model::{EventOrString, FluentBitJson, LogOrString};
+use crate::{klog, logfmt, postfix};
+
+/// Guesses the parser from the shape of a record that could not be assigned to an application
+/// by its metadata.
+pub fn sniff_parser(json: &FluentBitJson) -> Option<&'static str> {
+    let has = |key: &str| json.other.contains_key(key);
+
+    // Quarkus JSON logging as used by Keycloak
+    if has("loggerName") && has("sequence") {
+        return Some("keycloak");
+    }
+
+    // zap JSON logging as used by etcd. MetalLB logs look similar but name an operation or event.
+    let metallb_like = has("op") || matches!(json.event, Some(EventOrString::String(_)));
+    if has("ts") && has("caller") && has("msg") && !metallb_like {
+        return Some("etcd");
+    }
+
+    match &json.log {
+        Some(LogOrString::String(log)) if postfix::is_postfix_log(log) => Some("postfix"),
+        Some(LogOrString::String(log)) if klog::is_klog_log(log) => Some("klog"),
+        Some(LogOrString::String(log)) if logfmt::is_logfmt_log(log) => Some("logfmt"),
+        _ => None,
+    }
+}
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+use log::warn;
+
+use crate::config::Config;
+use crate::model::FluentBitJson;
+
+/// Pod annotation with the IANA timezone of timestamps without one, e.g. `Europe/Berlin`.
+pub const TIMEZONE_ANNOTATION: &str = "fluent-ecs.bieniek-it.de/timezone";
+
+/// Completes a timestamp that carries neither a year nor a timezone, like those of BSD syslog or
+/// klog.
+///
+/// The year is not logged. The adjacent year closest to the time the event arrived at fluent-bit
+/// is taken, e.g. the previous year for a december log arriving in january.
+pub fn complete_date(
+    month: u32,
+    day: u32,
+    time: NaiveTime,
+    event_date: &DateTime<FixedOffset>,
+    timezone: Tz,
+) -> Option<DateTime<FixedOffset>> {
+    let year = event_date.year();
+    [year - 1, year, year + 1]
+        .into_iter()
+        .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+        .filter_map(|date| {
+            let local = date.and_time(time);
+            // local times skipped by a DST change are taken as the time after the change
+            timezone.from_local_datetime(&local).earliest().or_else(|| {
+                timezone
+                    .from_local_datetime(&(local + Duration::hours(1)))
+                    .earliest()
+            })
+        })
+        .map(|date| date.fixed_offset())
+        .min_by_key(|date| date.signed_duration_since(*event_date).num_seconds().abs())
+}
+
+/// The timezone of timestamps without one. It is taken from the pod annotation
+/// [TIMEZONE_ANNOTATION], from the configuration or is UTC.
+pub fn timezone(json: &FluentBitJson, config: &Config) -> Tz {
+    let annotation = json
+        .kubernetes
+        .as_ref()
+        .and_then(|kubernetes| kubernetes.annotations.get(TIMEZONE_ANNOTATION))
+        .and_then(|timezone| timezone.as_str());
+    match annotation.or(config.timezone.as_deref()) {
+        Some(timezone) => timezone.parse().unwrap_or_else(|err| {
+            warn!("unknown timezone '{}': {}", timezone, err);
+            Tz::UTC
+        }),
+        None => Tz::UTC,
+    }
+}
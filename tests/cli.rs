@@ -0,0 +1,113 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use assert_json_diff::assert_json_eq;
+use serde_json::Value;
+
+const SOME_TIME: &str = "2023-11-16T13:27:38.555+01:00";
+
+fn spawn_cli(args: &[&str], stdin: &[u8]) -> Result<Output, String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_fluent-ecs"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("CLI could not be started: {}", err))?;
+
+    child
+        .stdin
+        .take()
+        .ok_or("stdin of the CLI is not available")?
+        .write_all(stdin)
+        .map_err(|err| format!("stdin could not be written: {}", err))?;
+
+    child
+        .wait_with_output()
+        .map_err(|err| format!("CLI failed: {}", err))
+}
+
+fn run_cli(args: &[&str], stdin: &[u8]) -> Result<String, String> {
+    let output = spawn_cli(args, stdin)?;
+    if !output.status.success() {
+        return Err(format!("CLI exited with {}", output.status));
+    }
+    String::from_utf8(output.stdout).map_err(|err| err.to_string())
+}
+
+fn read_json(path: &str) -> Result<Value, String> {
+    let content = fs::read(path).map_err(|err| format!("'{}' could not be read: {}", path, err))?;
+    serde_json::from_slice(&content).map_err(|err| format!("'{}' is not valid JSON: {}", path, err))
+}
+
+#[test]
+fn converts_ndjson_records() -> Result<(), String> {
+    let inputs = [
+        read_json("examples/etcd_took-in.json")?,
+        read_json("examples/postfix/smtp_transfer-in.json")?,
+    ];
+    let ndjson: String = inputs
+        .iter()
+        .map(|input| format!("{}\n\n", input))
+        .collect();
+
+    let output = run_cli(&["--time", SOME_TIME], ndjson.as_bytes())?;
+
+    let actual = output
+        .lines()
+        .map(|line| serde_json::from_str(line).map_err(|err| format!("Invalid output: {}", err)))
+        .collect::<Result<Vec<Value>, String>>()?;
+    let expected = vec![
+        read_json("examples/etcd_took-out.json")?,
+        read_json("examples/postfix/smtp_transfer-out.json")?,
+    ];
+    assert_json_eq!(Value::Array(actual), Value::Array(expected));
+
+    Ok(())
+}
+
+#[test]
+fn converts_raw_lines_with_forced_parser() -> Result<(), String> {
+    let output = run_cli(
+        &[
+            "--time", SOME_TIME, "--raw", "--parser", "postfix", "--tag", "mail",
+        ],
+        b"Nov 18 06:23:07 mail postfix/qmgr[81]: 1DC4D985C10: removed\n",
+    )?;
+
+    let actual: Value = serde_json::from_str(&output).map_err(|err| err.to_string())?;
+    assert_eq!(actual["event"]["module"], "postfix");
+    assert_eq!(actual["transaction"]["id"], "mail.1DC4D985C10");
+    assert_eq!(actual["labels"]["fluentbit_tag"], "mail");
+
+    Ok(())
+}
+
+#[test]
+fn converts_records_after_invalid_utf8() -> Result<(), String> {
+    let output = run_cli(
+        &["--time", SOME_TIME],
+        b"{\"log\":\"a\"}\n{\"log\":\"\xff\"}\n{\"log\":\"b\"}\n",
+    )?;
+
+    let actual = output
+        .lines()
+        .map(|line| serde_json::from_str(line).map_err(|err| format!("Invalid output: {}", err)))
+        .collect::<Result<Vec<Value>, String>>()?;
+    assert_eq!(actual.len(), 3);
+    assert_eq!(actual[0]["message"], "a");
+    assert_eq!(actual[1]["event"]["kind"], "pipeline_error");
+    assert_eq!(actual[2]["message"], "b");
+
+    Ok(())
+}
+
+#[test]
+fn rejects_unknown_parser() -> Result<(), String> {
+    let output = spawn_cli(&["--parser", "doesnotexist"], b"")?;
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+
+    Ok(())
+}